@@ -0,0 +1,330 @@
+// Tooltip preferences read from a JSON file, by default %APPDATA%\xclock\config.json.
+//
+// Every field is optional; fields not listed here (e.g. from newer versions) are ignored.
+// Example:
+//
+//     {
+//         "sections": ["week", "datetime"],
+//         "week_label": "Week {week}",
+//         "week_scheme": "us",
+//         "datetime_format": "%a %d %b %H:%M",
+//         "show_battery": true,
+//         "timezones": [{ "label": "NYC", "offset_hours": -5 }]
+//     }
+
+use serde::Deserialize;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+// An editor may still be writing the file, so a parse error is only reported once it persists
+const RELOAD_ATTEMPTS: u32 = 3;
+// The library's order when "sections" is absent
+const DEFAULT_SECTIONS: [&str; 3] = ["uptime", "week", "datetime"];
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub sections: Option<Vec<String>>,
+    pub week_label: Option<String>,
+    pub week_scheme: Option<String>,
+    pub datetime_format: Option<String>,
+    pub time_24h: Option<bool>,
+    pub uptime_precision: Option<String>,
+    pub show_battery: Option<bool>,
+    pub show_memory: Option<bool>,
+    pub show_day_of_year: Option<bool>,
+    pub show_moon_phase: Option<bool>,
+    pub show_network: Option<bool>,
+    pub line_decorations: Option<bool>,
+    pub timezones: Vec<TimezoneConfig>,
+    pub location: Option<LocationConfig>,
+    pub update_cooldown_ms: Option<u64>,
+    pub max_tooltip_width: Option<i32>,
+    pub activation_modifier: Option<String>,
+    pub line_separator: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TimezoneConfig {
+    pub label: String,
+    pub offset_hours: i32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LocationConfig {
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+// The lines the settings window can switch on and off
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContentToggles {
+    pub uptime: bool,
+    pub week: bool,
+    pub datetime: bool,
+    pub battery: bool,
+    pub memory: bool,
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(io::Error),
+    Parse(serde_json::Error),
+    // The file is valid JSON but not an object, so there is nowhere to put the settings
+    NotAnObject,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(e) => write!(f, "{}", e),
+            ConfigError::Parse(e) => write!(f, "invalid JSON: {}", e),
+            ConfigError::NotAnObject => write!(f, "expected a JSON object"),
+        }
+    }
+}
+
+impl ConfigError {
+    pub fn is_not_found(&self) -> bool {
+        matches!(self, ConfigError::Io(e) if e.kind() == io::ErrorKind::NotFound)
+    }
+}
+
+pub fn default_path() -> Option<PathBuf> {
+    std::env::var_os("APPDATA").map(|appdata| Path::new(&appdata).join("xclock").join("config.json"))
+}
+
+pub fn read(path: &Path) -> Result<Config, ConfigError> {
+    let text = fs::read_to_string(path).map_err(ConfigError::Io)?;
+    serde_json::from_str(&text).map_err(ConfigError::Parse)
+}
+
+/// Writes `toggles` into the config at `path`, creating it if needed and keeping every other field.
+pub fn save_toggles(path: &Path, toggles: &ContentToggles) -> Result<(), ConfigError> {
+    let mut root = match fs::read_to_string(path) {
+        Ok(text) => serde_json::from_str(&text).map_err(ConfigError::Parse)?,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => serde_json::Value::Object(Default::default()),
+        Err(e) => return Err(ConfigError::Io(e)),
+    };
+    let object = root.as_object_mut().ok_or(ConfigError::NotAnObject)?;
+
+    let mut sections: Vec<String> = match object.get("sections").and_then(|sections| sections.as_array()) {
+        Some(sections) => sections.iter().filter_map(|name| name.as_str()).map(str::to_string).collect(),
+        None => DEFAULT_SECTIONS.iter().map(|name| name.to_string()).collect(),
+    };
+    for (name, enabled) in [("uptime", toggles.uptime), ("week", toggles.week), ("datetime", toggles.datetime)] {
+        let present = sections.iter().any(|section| section == name);
+        if enabled && !present {
+            sections.push(name.to_string());
+        } else if !enabled && present {
+            sections.retain(|section| section != name);
+        }
+    }
+    object.insert("sections".to_string(), sections.into());
+    object.insert("show_battery".to_string(), toggles.battery.into());
+    object.insert("show_memory".to_string(), toggles.memory.into());
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(ConfigError::Io)?;
+    }
+    let text = serde_json::to_string_pretty(&root).map_err(ConfigError::Parse)?;
+    fs::write(path, text).map_err(ConfigError::Io)
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+/// Re-applies the config whenever the file's modification time changes, until `running` is cleared.
+pub fn watch(path: PathBuf, running: Arc<AtomicBool>) {
+    thread::spawn(move || {
+        let mut last_modified = modified(&path);
+        let mut failed_attempts = 0;
+
+        while running.load(Ordering::SeqCst) {
+            thread::sleep(WATCH_INTERVAL);
+
+            let current = modified(&path);
+            if current.is_none() || (current == last_modified && failed_attempts == 0) {
+                continue;
+            }
+
+            match read(&path) {
+                Ok(config) => {
+                    // Settings calls reach into the hook DLL, which only the message loop thread may reload
+                    let display = path.display().to_string();
+                    xclock::run_on_message_loop(move || {
+                        config.apply();
+                        println!("Reloaded config from {}", display);
+                    });
+                    failed_attempts = 0;
+                }
+                Err(e) => {
+                    failed_attempts += 1;
+                    if failed_attempts < RELOAD_ATTEMPTS {
+                        continue;
+                    }
+                    eprintln!("Failed to reload config {}: {} - keeping the previous settings", path.display(), e);
+                    failed_attempts = 0;
+                }
+            }
+            last_modified = current;
+        }
+    });
+}
+
+impl Config {
+    pub fn content_toggles(&self) -> ContentToggles {
+        let sections: Vec<&str> = match &self.sections {
+            Some(sections) => sections.iter().map(String::as_str).collect(),
+            None => DEFAULT_SECTIONS.to_vec(),
+        };
+        ContentToggles {
+            uptime: sections.contains(&"uptime"),
+            week: sections.contains(&"week"),
+            datetime: sections.contains(&"datetime"),
+            battery: self.show_battery.unwrap_or(sections.contains(&"battery")),
+            memory: self.show_memory.unwrap_or(sections.contains(&"memory")),
+        }
+    }
+
+    // Absent fields fall back to the library defaults, also when a reload removes them
+    pub fn apply(&self) {
+        // An empty list, e.g. saved by the settings window with every base line unchecked, means none
+        match &self.sections {
+            Some(sections) if sections.is_empty() => xclock::clear_sections(),
+            sections => {
+                let sections: Vec<&str> = sections.iter().flatten().map(String::as_str).collect();
+                if let Err(e) = xclock::set_section_order(&sections) {
+                    eprintln!("Config: {} - using the default sections", e);
+                    let _ = xclock::set_section_order(&[]);
+                }
+            }
+        }
+
+        xclock::set_week_label_format(self.week_label.as_deref().unwrap_or_default());
+        xclock::set_datetime_format(self.datetime_format.as_deref().unwrap_or_default());
+        xclock::set_time_24h(self.time_24h.unwrap_or(true));
+        xclock::set_line_separator(self.line_separator.as_deref().unwrap_or_default());
+
+        let scheme = match self.week_scheme.as_deref() {
+            None | Some("iso") => xclock::WeekScheme::Iso,
+            Some("us") => xclock::WeekScheme::UsSundayStart,
+            Some(other) => {
+                eprintln!("Config: unknown week_scheme '{}' - expected \"iso\" or \"us\"", other);
+                xclock::WeekScheme::Iso
+            }
+        };
+        xclock::set_week_scheme(scheme);
+
+        let precision = match self.uptime_precision.as_deref() {
+            None | Some("minutes") => xclock::UptimePrecision::Minutes,
+            Some("seconds") => xclock::UptimePrecision::Seconds,
+            Some(other) => {
+                eprintln!("Config: unknown uptime_precision '{}' - expected \"minutes\" or \"seconds\"", other);
+                xclock::UptimePrecision::Minutes
+            }
+        };
+        xclock::set_uptime_precision(precision);
+
+        let modifier = match self.activation_modifier.as_deref() {
+            None | Some("none") => None,
+            Some("shift") => Some(xclock::Modifier::Shift),
+            Some("ctrl") => Some(xclock::Modifier::Ctrl),
+            Some("alt") => Some(xclock::Modifier::Alt),
+            Some(other) => {
+                eprintln!("Config: unknown activation_modifier '{}' - expected \"shift\", \"ctrl\" or \"alt\"", other);
+                None
+            }
+        };
+        xclock::set_activation_modifier(modifier);
+
+        if let Some(enabled) = self.show_battery {
+            xclock::set_show_battery(enabled);
+        }
+        if let Some(enabled) = self.show_memory {
+            xclock::set_show_memory(enabled);
+        }
+        if let Some(enabled) = self.show_day_of_year {
+            xclock::set_show_day_of_year(enabled);
+        }
+        if let Some(enabled) = self.show_moon_phase {
+            xclock::set_show_moon_phase(enabled);
+        }
+        if let Some(enabled) = self.show_network {
+            xclock::set_show_network(enabled);
+        }
+
+        xclock::set_line_decorations(self.line_decorations.unwrap_or(false));
+
+        xclock::clear_secondary_timezones();
+        for zone in &self.timezones {
+            if let Err(e) = xclock::add_secondary_timezone(&zone.label, zone.offset_hours) {
+                eprintln!("Config: skipping timezone '{}': {}", zone.label, e);
+            }
+        }
+
+        match &self.location {
+            Some(location) => {
+                if let Err(e) = xclock::set_location(location.latitude, location.longitude) {
+                    eprintln!("Config: ignoring location: {}", e);
+                    xclock::clear_location();
+                }
+            }
+            None => xclock::clear_location(),
+        }
+
+        match self.update_cooldown_ms {
+            Some(ms) => xclock::set_update_cooldown(Duration::from_millis(ms)),
+            None => xclock::reset_update_cooldown(),
+        }
+        match self.max_tooltip_width {
+            Some(px) => xclock::set_max_tooltip_width(px),
+            None => xclock::reset_max_tooltip_width(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn toggles_from_library() -> ContentToggles {
+        let order = xclock::section_order();
+        let shown = |name: &str| order.iter().any(|section| section == name);
+        ContentToggles {
+            uptime: shown("uptime"),
+            week: shown("week"),
+            datetime: shown("datetime"),
+            battery: shown("battery"),
+            memory: shown("memory"),
+        }
+    }
+
+    #[test]
+    fn saved_toggles_are_applied_exactly() {
+        let path = std::env::temp_dir().join(format!("xclock-config-test-{}.json", std::process::id()));
+        for bits in 0..32u32 {
+            let toggles = ContentToggles {
+                uptime: bits & 1 != 0,
+                week: bits & 2 != 0,
+                datetime: bits & 4 != 0,
+                battery: bits & 8 != 0,
+                memory: bits & 16 != 0,
+            };
+            save_toggles(&path, &toggles).unwrap();
+            let config = read(&path).unwrap();
+            assert_eq!(config.content_toggles(), toggles);
+            config.apply();
+            assert_eq!(toggles_from_library(), toggles);
+        }
+        let _ = fs::remove_file(&path);
+    }
+}
@@ -1,31 +1,261 @@
+mod config;
+#[cfg(windows)]
+mod settings_window;
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
 use std::env;
+use std::io::{self, BufRead};
+use std::path::{Path, PathBuf};
 use std::process;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::thread;
 use std::time::Duration;
 
+// Prints library log records as plain console lines, like the CLI's own output
+struct ConsoleLogger;
+
+impl Log for ConsoleLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        if record.level() <= Level::Warn {
+            eprintln!("{}", record.args());
+        } else {
+            println!("{}", record.args());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: ConsoleLogger = ConsoleLogger;
+
+// 0: warnings and errors only, 1 (-v): progress such as the DLL location, 2+ (-vv): debug details
+fn init_logging(verbosity: usize) {
+    if log::set_logger(&LOGGER).is_ok() {
+        log::set_max_level(match verbosity {
+            0 => LevelFilter::Warn,
+            1 => LevelFilter::Info,
+            2 => LevelFilter::Debug,
+            _ => LevelFilter::Trace,
+        });
+    }
+}
+
+// Removes the verbosity flags from `args`, which may appear anywhere, and returns their count
+fn take_verbosity(args: &mut Vec<String>) -> usize {
+    let mut verbosity = 0;
+    args.retain(|arg| {
+        let count = match arg.as_str() {
+            "-v" | "--verbose" => 1,
+            "-vv" => 2,
+            "-vvv" => 3,
+            _ => return true,
+        };
+        verbosity += count;
+        false
+    });
+    verbosity
+}
+
+// Ctrl+C and the "q" console command both end up here; only the first request shuts down
+fn request_shutdown(running: &AtomicBool) {
+    if running.swap(false, Ordering::SeqCst) {
+        println!("\nShutting down...");
+        xclock::quit_message_loop();
+    }
+}
+
+fn spawn_console_commands(running: Arc<AtomicBool>) {
+    thread::spawn(move || {
+        for line in io::stdin().lock().lines() {
+            let Ok(line) = line else { break };
+            match line.trim() {
+                "q" | "quit" => {
+                    request_shutdown(&running);
+                    break;
+                }
+                // The restart reloads the DLL, so it runs on the message loop thread with every other DLL call
+                "r" | "restart" => xclock::run_on_message_loop(|| match xclock::restart_monitoring() {
+                    Ok(()) => println!("Hook restarted."),
+                    Err(e) => eprintln!("Failed to restart hook: {}", e),
+                }),
+                _ => {}
+            }
+        }
+    });
+}
+
 fn print_help() {
     println!("Enhanced Windows Clock Hover Hook CLI");
     println!("====================================");
     println!();
     println!("USAGE:");
-    println!("    xclock-cli [COMMAND]");
+    println!("    xclock-cli [COMMAND] [OPTIONS]");
     println!();
     println!("COMMANDS:");
     println!("    start     Start the clock hover hook");
     println!("    stop      Stop the clock hover hook (if running)");
+    println!("    restart   Same as start; to restart a running hook, type r + Enter in its console");
     println!("    status    Check if the hook is running");
+    println!("    detect    List the taskbar and clock windows found, without installing the hook");
+    println!("    test      Load the hook DLL and print the lines it would add to the clock tooltip");
+    println!("    settings  Choose the tooltip lines in a small window and save them to the config file");
     println!("    help      Show this help message");
     println!();
+    println!("OPTIONS:");
+    println!("    -v, --verbose     Show progress details; -vv adds debug output");
+    println!("    --config <FILE>   Read tooltip settings from FILE (start, restart, test, settings)");
+    println!();
+    println!("Without --config, tooltip settings are read from %APPDATA%\\xclock\\config.json if it exists.");
+    println!("The config file is reloaded automatically when it changes.");
+    println!("While running, type q + Enter to quit or r + Enter to restart the hook.");
+    println!();
     println!("EXAMPLES:");
     println!("    xclock-cli start    # Start monitoring the clock");
+    println!("    xclock-cli start --config work.json  # Use a per-profile config");
     println!("    xclock-cli stop     # Stop the hook");
     println!("    xclock-cli status   # Check running status");
+    println!("    xclock-cli detect   # Check clock detection for a bug report");
+    println!("    xclock-cli test     # Check that the DLL loads and generates tooltip text");
+    println!("    xclock-cli settings # Pick the tooltip lines; a running hook reloads them");
+}
+
+// Options following the start/restart/test command; exits on anything it doesn't understand
+fn parse_start_options(options: &[String]) -> Option<PathBuf> {
+    let mut config_path = None;
+    let mut options = options.iter();
+    while let Some(option) = options.next() {
+        match option.as_str() {
+            "--config" => match options.next() {
+                Some(path) => config_path = Some(PathBuf::from(path)),
+                None => {
+                    eprintln!("--config needs a file path");
+                    process::exit(1);
+                }
+            },
+            _ => {
+                eprintln!("Unknown option: {}", option);
+                eprintln!("Use 'xclock-cli help' for usage information.");
+                process::exit(1);
+            }
+        }
+    }
+    config_path
+}
+
+// An explicit path must exist. A missing default file is normal; anything else is reported and
+// the defaults are kept. Returns the path to watch for changes
+fn load_config(explicit_path: Option<&Path>) -> Option<PathBuf> {
+    let path = match explicit_path {
+        Some(path) => {
+            if !path.is_file() {
+                eprintln!("Config file not found: {}", path.display());
+                process::exit(1);
+            }
+            path.to_path_buf()
+        }
+        None => config::default_path()?,
+    };
+
+    match config::read(&path) {
+        Ok(config) => {
+            config.apply();
+            println!("Loaded config from {}", path.display());
+        }
+        Err(e) if e.is_not_found() => {}
+        Err(e) => eprintln!("Failed to load config {}: {} - using defaults", path.display(), e),
+    }
+    Some(path)
+}
+
+// Generates the tooltip text without installing the hook or needing a clock to hover
+fn self_test(config_path: Option<&Path>) {
+    load_config(config_path);
+
+    let text = xclock::current_tooltip_text();
+    if text.is_empty() {
+        eprintln!("FAILED: no tooltip text - is xclock_hook.dll next to xclock-cli.exe and a section enabled?");
+        process::exit(1);
+    }
+    println!("{}", text);
+    println!("OK");
+}
+
+// Saving writes the config file, which a running `start` picks up through its config watcher
+#[cfg(windows)]
+fn edit_settings(config_path: Option<&Path>) {
+    let Some(path) = config_path.map(Path::to_path_buf).or_else(config::default_path) else {
+        eprintln!("No config file location - pass --config <FILE> or set APPDATA");
+        process::exit(1);
+    };
+    if let Err(e) = settings_window::run(&path) {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
+
+fn run(config_path: Option<&Path>) {
+    println!("Starting Windows Clock Hover Hook...");
+    let config_path = load_config(config_path);
+    
+    // Set up Ctrl+C handler
+    let running = Arc::new(AtomicBool::new(true));
+    let r = running.clone();
+    
+    ctrlc::set_handler(move || request_shutdown(&r)).expect("Error setting Ctrl+C handler");
+
+    // Start the hook
+    match xclock::start_monitoring() {
+        Ok(()) => {
+            println!("Hook started successfully!");
+            println!("Hover over the system clock to see extended information.");
+            if xclock::is_toggle_hotkey_registered() {
+                println!("Press Ctrl+Alt+C to pause or resume.");
+            }
+            match xclock::add_tray_icon() {
+                Ok(()) => println!("Use the tray icon to pause, resume or exit, or type q + Enter / press Ctrl+C."),
+                Err(e) => {
+                    eprintln!("Failed to add tray icon: {}", e);
+                    println!("Type q + Enter or press Ctrl+C to exit.");
+                }
+            }
+            spawn_console_commands(running.clone());
+            if let Some(path) = config_path {
+                config::watch(path, running.clone());
+            }
+            
+            // Main message loop
+            while running.load(Ordering::SeqCst) && xclock::is_running() {
+                match xclock::message_loop() {
+                    Ok(()) => break,
+                    Err(_) => {
+                        thread::sleep(Duration::from_millis(10));
+                    }
+                }
+            }
+            
+            // Clean shutdown
+            xclock::remove_tray_icon();
+            xclock::stop_monitoring();
+            println!("Program terminated.");
+        },
+        Err(e) => {
+            eprintln!("Failed to start hook: {} (elevated: {})", e, if xclock::is_elevated() { "yes" } else { "no" });
+            process::exit(1);
+        }
+    }
 }
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
+    let mut args: Vec<String> = env::args().collect();
+    init_logging(take_verbosity(&mut args));
     
     if args.len() < 2 {
         print_help();
@@ -33,44 +263,11 @@ fn main() {
     }
 
     match args[1].as_str() {
-        "start" => {
-            println!("Starting Windows Clock Hover Hook...");
-            
-            // Set up Ctrl+C handler
-            let running = Arc::new(AtomicBool::new(true));
-            let r = running.clone();
-            
-            ctrlc::set_handler(move || {
-                println!("\nShutting down...");
-                r.store(false, Ordering::SeqCst);
-            }).expect("Error setting Ctrl+C handler");
-
-            // Start the hook
-            match xclock::start_monitoring() {
-                Ok(()) => {
-                    println!("Hook started successfully!");
-                    println!("Hover over the system clock to see extended information.");
-                    println!("Press Ctrl+C to exit.");
-                    
-                    // Main message loop
-                    while running.load(Ordering::SeqCst) && xclock::is_running() {
-                        match xclock::message_loop() {
-                            Ok(()) => break,
-                            Err(_) => {
-                                thread::sleep(Duration::from_millis(10));
-                            }
-                        }
-                    }
-                    
-                    // Clean shutdown
-                    xclock::stop_monitoring();
-                    println!("Program terminated.");
-                },
-                Err(e) => {
-                    eprintln!("Failed to start hook: {}", e);
-                    process::exit(1);
-                }
-            }
+        // "restart" stays as an alias: the hook belongs to the process that installed it, so a new
+        // process has nothing of its own to stop first
+        "start" | "restart" => {
+            let config_path = parse_start_options(&args[2..]);
+            run(config_path.as_deref());
         },
         
         "stop" => {
@@ -85,8 +282,31 @@ fn main() {
             } else {
                 println!("Clock hover hook is currently STOPPED");
             }
+            println!("Elevated: {}", if xclock::is_elevated() { "yes" } else { "no" });
+        },
+        
+        "detect" => {
+            println!("Elevated: {}", if xclock::is_elevated() { "yes" } else { "no" });
+            let windows = xclock::detect_clock_windows();
+            if windows.is_empty() {
+                println!("No taskbar or clock windows found.");
+            }
+            for (class_name, rect) in windows {
+                println!("{:<24} ({}, {}) - ({}, {})", class_name, rect.left, rect.top, rect.right, rect.bottom);
+            }
+        },
+        
+        "test" => {
+            let config_path = parse_start_options(&args[2..]);
+            self_test(config_path.as_deref());
         },
         
+        #[cfg(windows)]
+        "settings" => {
+            let config_path = parse_start_options(&args[2..]);
+            edit_settings(config_path.as_deref());
+        },
+
         "help" | "--help" | "-h" => {
             print_help();
         },
@@ -0,0 +1,176 @@
+// A small window for switching tooltip lines on and off without editing the JSON config.
+//
+// Saving writes the config file; a running `xclock-cli start` picks the change up through its
+// config watcher, so there is nothing to apply here directly.
+
+#![allow(unsafe_op_in_unsafe_fn)]
+
+use std::path::Path;
+use std::ptr;
+use std::sync::Mutex;
+use winapi::shared::minwindef::{LOWORD, LPARAM, LRESULT, UINT, WPARAM};
+use winapi::shared::windef::HWND;
+use winapi::shared::winerror::ERROR_CLASS_ALREADY_EXISTS;
+use winapi::um::errhandlingapi::GetLastError;
+use winapi::um::libloaderapi::GetModuleHandleW;
+use winapi::um::wingdi::{GetStockObject, DEFAULT_GUI_FONT};
+use winapi::um::winuser::*;
+
+use xclock_util::wide::to_wide;
+
+use crate::config::{self, ContentToggles};
+
+const CLASS_NAME: &str = "XClockSettings";
+const WINDOW_WIDTH: i32 = 280;
+const WINDOW_HEIGHT: i32 = 250;
+const MARGIN: i32 = 16;
+const ROW_HEIGHT: i32 = 24;
+const BUTTON_WIDTH: i32 = 80;
+
+// Control IDs of the checkboxes, in display order
+const CHECKBOXES: [(u16, &str); 5] = [
+    (101, "Uptime"),
+    (102, "Week number"),
+    (103, "Date and time"),
+    (104, "Battery"),
+    (105, "CPU and memory"),
+];
+
+// Set by the window procedure when Save is clicked, read once the window has closed
+static SAVED: Mutex<Option<ContentToggles>> = Mutex::new(None);
+
+/// Shows the settings window for the config at `path` and writes it back when Save is clicked.
+pub fn run(path: &Path) -> Result<(), String> {
+    let toggles = match config::read(path) {
+        Ok(config) => config.content_toggles(),
+        Err(e) if e.is_not_found() => config::Config::default().content_toggles(),
+        Err(e) => return Err(format!("Failed to load config {}: {}", path.display(), e)),
+    };
+
+    unsafe { show(&toggles)? };
+
+    let saved = SAVED.lock().unwrap_or_else(|e| e.into_inner()).take();
+    if let Some(toggles) = saved {
+        config::save_toggles(path, &toggles).map_err(|e| format!("Failed to save config {}: {}", path.display(), e))?;
+        println!("Saved config to {}", path.display());
+    }
+    Ok(())
+}
+
+unsafe fn show(toggles: &ContentToggles) -> Result<(), String> {
+    let instance = GetModuleHandleW(ptr::null());
+    let class_name = to_wide(CLASS_NAME);
+    let mut wnd_class: WNDCLASSW = std::mem::zeroed();
+    wnd_class.lpfnWndProc = Some(window_proc);
+    wnd_class.hInstance = instance;
+    wnd_class.hCursor = LoadCursorW(ptr::null_mut(), IDC_ARROW);
+    wnd_class.hbrBackground = (COLOR_BTNFACE + 1) as _;
+    wnd_class.lpszClassName = class_name.as_ptr();
+    // The class may still be registered by an earlier window in this process
+    if RegisterClassW(&wnd_class) == 0 {
+        let error = GetLastError();
+        if error != ERROR_CLASS_ALREADY_EXISTS {
+            return Err(format!("Failed to register the settings window class (error {})", error));
+        }
+    }
+
+    let title = to_wide("xclock settings");
+    let hwnd = CreateWindowExW(
+        WS_EX_DLGMODALFRAME,
+        class_name.as_ptr(),
+        title.as_ptr(),
+        WS_OVERLAPPED | WS_CAPTION | WS_SYSMENU,
+        CW_USEDEFAULT,
+        CW_USEDEFAULT,
+        WINDOW_WIDTH,
+        WINDOW_HEIGHT,
+        ptr::null_mut(),
+        ptr::null_mut(),
+        instance,
+        ptr::null_mut(),
+    );
+    if hwnd.is_null() {
+        return Err(format!("Failed to create the settings window (error {})", GetLastError()));
+    }
+
+    let states = [toggles.uptime, toggles.week, toggles.datetime, toggles.battery, toggles.memory];
+    for (row, ((id, label), checked)) in CHECKBOXES.iter().zip(states).enumerate() {
+        let top = MARGIN + row as i32 * ROW_HEIGHT;
+        let checkbox = add_control(hwnd, "BUTTON", label, BS_AUTOCHECKBOX | WS_TABSTOP, *id, MARGIN, top, WINDOW_WIDTH - 2 * MARGIN);
+        let state = if checked { BST_CHECKED } else { BST_UNCHECKED };
+        SendMessageW(checkbox, BM_SETCHECK, state as WPARAM, 0);
+    }
+
+    let buttons_top = MARGIN + CHECKBOXES.len() as i32 * ROW_HEIGHT + MARGIN / 2;
+    add_control(hwnd, "BUTTON", "Save", BS_DEFPUSHBUTTON | WS_TABSTOP, IDOK as u16, MARGIN, buttons_top, BUTTON_WIDTH);
+    let cancel_left = MARGIN + BUTTON_WIDTH + MARGIN / 2;
+    add_control(hwnd, "BUTTON", "Cancel", BS_PUSHBUTTON | WS_TABSTOP, IDCANCEL as u16, cancel_left, buttons_top, BUTTON_WIDTH);
+
+    ShowWindow(hwnd, SW_SHOW);
+    let mut msg: MSG = std::mem::zeroed();
+    while GetMessageW(&mut msg, ptr::null_mut(), 0, 0) > 0 {
+        // Tab, Enter and Escape behave like in a dialog box
+        if IsDialogMessageW(hwnd, &mut msg) == 0 {
+            TranslateMessage(&msg);
+            DispatchMessageW(&msg);
+        }
+    }
+
+    UnregisterClassW(class_name.as_ptr(), instance);
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+unsafe fn add_control(parent: HWND, class: &str, text: &str, style: u32, id: u16, x: i32, y: i32, width: i32) -> HWND {
+    let class = to_wide(class);
+    let text = to_wide(text);
+    let control = CreateWindowExW(
+        0,
+        class.as_ptr(),
+        text.as_ptr(),
+        WS_CHILD | WS_VISIBLE | style,
+        x,
+        y,
+        width,
+        ROW_HEIGHT - 2,
+        parent,
+        id as usize as _,
+        GetModuleHandleW(ptr::null()),
+        ptr::null_mut(),
+    );
+    SendMessageW(control, WM_SETFONT, GetStockObject(DEFAULT_GUI_FONT as i32) as WPARAM, 1);
+    control
+}
+
+unsafe fn read_toggles(hwnd: HWND) -> ContentToggles {
+    let checked = |index: usize| IsDlgButtonChecked(hwnd, CHECKBOXES[index].0 as i32) as usize == BST_CHECKED;
+    ContentToggles {
+        uptime: checked(0),
+        week: checked(1),
+        datetime: checked(2),
+        battery: checked(3),
+        memory: checked(4),
+    }
+}
+
+unsafe extern "system" fn window_proc(hwnd: HWND, msg: UINT, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    match msg {
+        WM_COMMAND => match LOWORD(wparam as u32) as i32 {
+            IDOK => {
+                *SAVED.lock().unwrap_or_else(|e| e.into_inner()) = Some(read_toggles(hwnd));
+                DestroyWindow(hwnd);
+                0
+            }
+            IDCANCEL => {
+                DestroyWindow(hwnd);
+                0
+            }
+            _ => DefWindowProcW(hwnd, msg, wparam, lparam),
+        },
+        WM_DESTROY => {
+            PostQuitMessage(0);
+            0
+        }
+        _ => DefWindowProcW(hwnd, msg, wparam, lparam),
+    }
+}
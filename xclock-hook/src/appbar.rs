@@ -0,0 +1,84 @@
+// Where the shell says the primary taskbar is docked, from the appbar API.
+//
+// SHAppBarMessage answers from Explorer's own bookkeeping, so unlike guessing from the screen size
+// it knows about taskbars docked at the top or sides and on any monitor.
+
+use winapi::shared::windef::RECT;
+use winapi::um::shellapi::{SHAppBarMessage, ABE_BOTTOM, ABE_LEFT, ABE_RIGHT, ABE_TOP, ABM_GETTASKBARPOS, APPBARDATA};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AppBarEdge {
+    Left,
+    Top,
+    Right,
+    Bottom,
+}
+
+// Docking edge and screen rectangle of the primary taskbar, None if the shell doesn't answer
+pub fn get_taskbar() -> Option<(AppBarEdge, RECT)> {
+    let mut data: APPBARDATA = unsafe { std::mem::zeroed() };
+    data.cbSize = std::mem::size_of::<APPBARDATA>() as u32;
+    if unsafe { SHAppBarMessage(ABM_GETTASKBARPOS, &mut data) } == 0 {
+        return None;
+    }
+    taskbar_from(&data)
+}
+
+fn taskbar_from(data: &APPBARDATA) -> Option<(AppBarEdge, RECT)> {
+    let edge = match data.uEdge {
+        ABE_LEFT => AppBarEdge::Left,
+        ABE_TOP => AppBarEdge::Top,
+        ABE_RIGHT => AppBarEdge::Right,
+        ABE_BOTTOM => AppBarEdge::Bottom,
+        _ => return None,
+    };
+    let rect = data.rc;
+    if rect.right <= rect.left || rect.bottom <= rect.top {
+        return None;
+    }
+    Some((edge, rect))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn appbar(edge: u32, left: i32, top: i32, right: i32, bottom: i32) -> APPBARDATA {
+        let mut data: APPBARDATA = unsafe { std::mem::zeroed() };
+        data.uEdge = edge;
+        data.rc = RECT { left, top, right, bottom };
+        data
+    }
+
+    fn rect_of(data: &APPBARDATA) -> (i32, i32, i32, i32) {
+        let (_, rect) = taskbar_from(data).unwrap();
+        (rect.left, rect.top, rect.right, rect.bottom)
+    }
+
+    #[test]
+    fn every_docking_edge() {
+        let cases = [
+            (ABE_BOTTOM, AppBarEdge::Bottom, (0, 1032, 1920, 1080)),
+            (ABE_TOP, AppBarEdge::Top, (0, 0, 1920, 48)),
+            (ABE_LEFT, AppBarEdge::Left, (0, 0, 62, 1080)),
+            (ABE_RIGHT, AppBarEdge::Right, (1858, 0, 1920, 1080)),
+        ];
+        for (abe, edge, (left, top, right, bottom)) in cases {
+            let data = appbar(abe, left, top, right, bottom);
+            assert_eq!(taskbar_from(&data).map(|(edge, _)| edge), Some(edge));
+            assert_eq!(rect_of(&data), (left, top, right, bottom));
+        }
+    }
+
+    #[test]
+    fn taskbar_on_a_monitor_left_of_the_primary() {
+        let data = appbar(ABE_BOTTOM, -1920, 1032, 0, 1080);
+        assert_eq!(rect_of(&data), (-1920, 1032, 0, 1080));
+    }
+
+    #[test]
+    fn unknown_edge_or_empty_rect() {
+        assert!(taskbar_from(&appbar(7, 0, 1032, 1920, 1080)).is_none());
+        assert!(taskbar_from(&appbar(ABE_BOTTOM, 0, 0, 0, 0)).is_none());
+    }
+}
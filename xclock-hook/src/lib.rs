@@ -1,18 +1,30 @@
 #![allow(unsafe_op_in_unsafe_fn)]
+#![allow(clippy::missing_safety_doc)]
+
+mod appbar;
+mod sections;
+mod settings;
+mod time;
 
-use chrono::Datelike;
-use std::ffi::OsString;
-use std::os::windows::ffi::OsStringExt;
 use std::ptr;
-use std::sync::atomic::{AtomicBool, AtomicPtr, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicPtr, AtomicU32, AtomicU64, Ordering};
+use std::sync::{mpsc, Mutex};
 use std::time::{Duration, Instant};
-use winapi::shared::minwindef::{BOOL, DWORD, HINSTANCE, LPARAM, LRESULT, WPARAM};
+use winapi::shared::minwindef::{BOOL, DWORD, HINSTANCE, HINSTANCE__, HMODULE, LPARAM, LPVOID, LRESULT, WPARAM};
 use winapi::shared::windef::{HWND, RECT, HHOOK__};
-use winapi::um::sysinfoapi::GetTickCount;
+use winapi::um::commctrl::TTM_SETMAXTIPWIDTH;
+use winapi::um::shellapi::{SHAppBarMessage, ABM_GETSTATE, ABS_AUTOHIDE, APPBARDATA};
 use winapi::um::winuser::*;
 use winapi::um::debugapi::OutputDebugStringA;
 use winapi::um::errhandlingapi::GetLastError;
+use winapi::um::handleapi::CloseHandle;
+use winapi::um::libloaderapi::{
+    FreeLibrary, FreeLibraryAndExitThread, GetModuleHandleExW, GET_MODULE_HANDLE_EX_FLAG_FROM_ADDRESS,
+};
+use winapi::um::processthreadsapi::CreateThread;
+use winapi::um::sysinfoapi::GetTickCount64;
 use std::ffi::CString;
+use xclock_util::wide::{from_wide, to_wide};
 
 // DLL attach/detach constants
 const DLL_PROCESS_ATTACH: u32 = 1;
@@ -21,9 +33,40 @@ const DLL_PROCESS_DETACH: u32 = 0;
 // Global state for the hook
 static HOOK_INSTALLED: AtomicBool = AtomicBool::new(false);
 static HOOK_HANDLE: AtomicPtr<HHOOK__> = AtomicPtr::new(ptr::null_mut());
-static mut DLL_INSTANCE: HINSTANCE = ptr::null_mut();
-static mut LAST_TOOLTIP_UPDATE: Option<Instant> = None;
-const TOOLTIP_UPDATE_COOLDOWN: Duration = Duration::from_millis(500);
+// Set once in DLL_PROCESS_ATTACH, read by InstallHook
+static DLL_INSTANCE: AtomicPtr<HINSTANCE__> = AtomicPtr::new(ptr::null_mut());
+// GetLastError from the most recent failed SetWindowsHookExW, 0 after a successful install
+static LAST_INSTALL_ERROR: AtomicU32 = AtomicU32::new(0);
+// GetTickCount64 value of the last tooltip rewrite in this process, 0 before the first
+static LAST_TOOLTIP_UPDATE_MS: AtomicU64 = AtomicU64::new(0);
+const DEFAULT_TOOLTIP_UPDATE_COOLDOWN_MS: u64 = 500;
+// Classic tooltips wrap at word boundaries beyond this width unless the host sets another
+const DEFAULT_MAX_TIP_WIDTH: i32 = 400;
+
+// Primary and per-monitor taskbars (the latter exist when "show taskbar on all displays" is on)
+const TASKBAR_CLASSES: [&str; 2] = ["Shell_TrayWnd", "Shell_SecondaryTrayWnd"];
+// How far from a taskbar a tooltip may appear and still count as the clock tooltip
+const TASKBAR_PROXIMITY: i32 = 200;
+// An auto-hidden taskbar leaves a strip about this thick on screen to catch the mouse
+const AUTOHIDE_STRIP: i32 = 4;
+
+const TOOLTIP_CLASSES: [&str; 2] = ["tooltips_class32", "Xaml_WindowedPopupClass"];
+// Tooltips seen visible by the previous PollTooltips call
+static POLLED_TOOLTIPS: Mutex<Vec<usize>> = Mutex::new(Vec::new());
+// Tooltips we've already extended: (HWND, original native text, text we wrote)
+static AUGMENTED_TOOLTIPS: Mutex<Vec<(usize, String, String)>> = Mutex::new(Vec::new());
+// Worker that modifies tooltips created in this process, None while no worker is running
+static TOOLTIP_WORKER: Mutex<Option<mpsc::Sender<(usize, Instant)>>> = Mutex::new(None);
+// Lets a new tooltip finish initializing its text before we read it
+const TOOLTIP_SETTLE_DELAY: Duration = Duration::from_millis(100);
+// The worker exits after this long without tooltips so no thread lingers in idle processes, and so
+// an uninstalled hook's DLL, which the worker keeps loaded, goes away soon after
+const TOOLTIP_WORKER_IDLE: Duration = Duration::from_secs(1);
+// Text messages may target a tooltip in another process when polling, so don't block on a hung owner
+const TEXT_MESSAGE_TIMEOUT_MS: u32 = 100;
+// WM_GETTEXT buffer sizes in UTF-16 units; tooltips beyond the maximum are read truncated
+const MIN_TEXT_BUFFER: usize = 512;
+const MAX_TEXT_BUFFER: usize = 32 * 1024;
 
 // Debug logging function
 unsafe fn debug_log(msg: &str) {
@@ -40,22 +83,21 @@ unsafe fn debug_logf(msg: &str, args: &[&dyn std::fmt::Display]) {
     debug_log(&formatted);
 }
 
-fn utf16_to_string(utf16: &[u16]) -> String {
-    OsString::from_wide(utf16)
-        .to_string_lossy()
-        .trim_end_matches('\0')
-        .to_string()
-}
-
-fn string_to_utf16(s: &str) -> Vec<u16> {
-    s.encode_utf16().chain(std::iter::once(0)).collect()
+// Reads a NUL-terminated UTF-16 string passed in by the main application
+unsafe fn utf16_ptr_to_string(ptr: *const u16) -> String {
+    if ptr.is_null() {
+        return String::new();
+    }
+    let len = (0..).take_while(|&i| *ptr.add(i) != 0).count();
+    from_wide(std::slice::from_raw_parts(ptr, len))
 }
 
 unsafe fn get_window_class_name(hwnd: HWND) -> String {
-    let mut class_name = [0u16; 256];
+    // Class names are limited to 256 characters, so this can't truncate
+    let mut class_name = [0u16; 257];
     let len = GetClassNameW(hwnd, class_name.as_mut_ptr(), class_name.len() as i32);
     if len > 0 {
-        let name = utf16_to_string(&class_name[..len as usize]);
+        let name = from_wide(&class_name[..len as usize]);
         if name == "tooltips_class32" {
             debug_logf("Found tooltip window class for HWND {0}: {1}", &[&(hwnd as usize), &name]);
         }
@@ -67,18 +109,121 @@ unsafe fn get_window_class_name(hwnd: HWND) -> String {
 }
 
 unsafe fn get_window_text(hwnd: HWND) -> String {
-    let mut text = [0u16; 512];
-    let len = GetWindowTextW(hwnd, text.as_mut_ptr(), text.len() as i32);
-    if len > 0 {
-        let window_text = utf16_to_string(&text[..len as usize]);
+    // WM_GETTEXT rather than GetWindowTextW, which doesn't query windows owned by other processes
+    let mut expected_len: usize = 0;
+    SendMessageTimeoutW(hwnd, WM_GETTEXTLENGTH, 0, 0, SMTO_ABORTIFHUNG, TEXT_MESSAGE_TIMEOUT_MS, &mut expected_len);
+
+    let mut capacity = (expected_len + 1).max(MIN_TEXT_BUFFER);
+    loop {
+        let mut text = vec![0u16; capacity];
+        let mut len: usize = 0;
+        let result = SendMessageTimeoutW(hwnd, WM_GETTEXT, text.len(), text.as_mut_ptr() as LPARAM,
+                                         SMTO_ABORTIFHUNG, TEXT_MESSAGE_TIMEOUT_MS, &mut len);
+        if result == 0 || len == 0 {
+            debug_logf("No window text for HWND {0}", &[&(hwnd as usize)]);
+            return String::new();
+        }
+
+        // A full buffer means the text may have grown since WM_GETTEXTLENGTH and been cut off
+        if len >= capacity - 1 && capacity < MAX_TEXT_BUFFER {
+            capacity = (capacity * 2).min(MAX_TEXT_BUFFER);
+            continue;
+        }
+
+        let window_text = from_wide(&text[..len.min(capacity)]);
         debug_logf("Window text for HWND {0}: '{1}'", &[&(hwnd as usize), &window_text]);
-        window_text
-    } else {
-        debug_logf("No window text for HWND {0}", &[&(hwnd as usize)]);
-        String::new()
+        return window_text;
     }
 }
 
+// WM_SETTEXT rather than SetWindowTextW, which can't change the text of another process's window
+unsafe fn set_window_text(hwnd: HWND, text: *const u16) -> bool {
+    let mut result: usize = 0;
+    SendMessageTimeoutW(hwnd, WM_SETTEXT, 0, text as LPARAM,
+                        SMTO_ABORTIFHUNG, TEXT_MESSAGE_TIMEOUT_MS, &mut result) != 0 && result != 0
+}
+
+// TTM_SETMAXTIPWIDTH value: the configured cap, or -1 to let lines grow as wide as they need
+fn max_tip_width() -> i32 {
+    match settings::get().map(|settings| settings.max_tip_width.load(Ordering::Relaxed)) {
+        Some(1) => -1,
+        Some(stored) if stored != 0 => i32::try_from(stored - 1).unwrap_or(i32::MAX),
+        // Without a newline separator the sections form one row, which a width cap would wrap
+        _ if !sections::line_separator().contains('\n') => -1,
+        _ => DEFAULT_MAX_TIP_WIDTH,
+    }
+}
+
+unsafe fn find_taskbar_rects() -> Vec<(HWND, RECT)> {
+    let mut rects = Vec::new();
+
+    for class in TASKBAR_CLASSES {
+        let class_utf16 = to_wide(class);
+        let mut hwnd = FindWindowExW(ptr::null_mut(), ptr::null_mut(), class_utf16.as_ptr(), ptr::null());
+        while !hwnd.is_null() {
+            let mut rect = RECT {
+                left: 0,
+                top: 0,
+                right: 0,
+                bottom: 0,
+            };
+            if GetWindowRect(hwnd, &mut rect) != 0 {
+                debug_logf("Found taskbar {0} HWND {1}: rect({2},{3},{4},{5})",
+                          &[&class, &(hwnd as usize), &rect.left, &rect.top, &rect.right, &rect.bottom]);
+                rects.push((hwnd, rect));
+            }
+            hwnd = FindWindowExW(ptr::null_mut(), hwnd, class_utf16.as_ptr(), ptr::null());
+        }
+    }
+
+    rects
+}
+
+fn is_taskbar_auto_hide() -> bool {
+    let mut data: APPBARDATA = unsafe { std::mem::zeroed() };
+    data.cbSize = std::mem::size_of::<APPBARDATA>() as u32;
+    let state = unsafe { SHAppBarMessage(ABM_GETSTATE, &mut data) };
+    state as u32 & ABS_AUTOHIDE != 0
+}
+
+// Hidden taskbars, and auto-hide taskbars slid off screen, leave nothing to hover: a tooltip near
+// that screen edge belongs to something else. They count again once they slide back in
+unsafe fn is_taskbar_showing(hwnd: HWND, rect: &RECT, auto_hide: bool) -> bool {
+    if IsWindowVisible(hwnd) == 0 {
+        return false;
+    }
+    if !auto_hide {
+        return true;
+    }
+
+    let monitor = MonitorFromRect(rect, MONITOR_DEFAULTTONEAREST);
+    let mut info: MONITORINFO = std::mem::zeroed();
+    info.cbSize = std::mem::size_of::<MONITORINFO>() as u32;
+    if GetMonitorInfoW(monitor, &mut info) == 0 {
+        return true;
+    }
+    let mut on_screen = RECT { left: 0, top: 0, right: 0, bottom: 0 };
+    if IntersectRect(&mut on_screen, rect, &info.rcMonitor) == 0 {
+        return false;
+    }
+    (on_screen.right - on_screen.left).min(on_screen.bottom - on_screen.top) > AUTOHIDE_STRIP
+}
+
+unsafe fn is_near_taskbar(taskbar: &RECT, rect: &RECT) -> bool {
+    let mut area = *taskbar;
+    InflateRect(&mut area, TASKBAR_PROXIMITY, TASKBAR_PROXIMITY);
+    let mut overlap = RECT {
+        left: 0,
+        top: 0,
+        right: 0,
+        bottom: 0,
+    };
+    IntersectRect(&mut overlap, &area, rect) != 0
+}
+
+// The clock tooltip is told apart by position alone: it must be near a visible taskbar. The clock
+// itself is never located here, so there is no clock lookup that could fail; the xclock library's
+// UI Automation ClockButton lookup only serves its detect_clock_windows diagnostics
 unsafe fn is_tooltip_in_taskbar_area(hwnd: HWND) -> bool {
     let mut rect = RECT {
         left: 0,
@@ -87,59 +232,100 @@ unsafe fn is_tooltip_in_taskbar_area(hwnd: HWND) -> bool {
         bottom: 0,
     };
     
-    if GetWindowRect(hwnd, &mut rect) != 0 {
-        let screen_height = GetSystemMetrics(SM_CYSCREEN);
-        let is_in_taskbar = rect.top > screen_height - 200;
-        debug_logf("Tooltip position check - HWND {0}: rect({1},{2},{3},{4}), screen_height={5}, in_taskbar={6}", 
-                  &[&(hwnd as usize), &rect.left, &rect.top, &rect.right, &rect.bottom, &screen_height, &is_in_taskbar]);
-        is_in_taskbar
-    } else {
+    if GetWindowRect(hwnd, &mut rect) == 0 {
         debug_logf("Failed to get window rect for HWND {0}", &[&(hwnd as usize)]);
-        false
+        return false;
     }
-}
 
-unsafe fn should_update_tooltip() -> bool {
-    if let Some(last_update) = LAST_TOOLTIP_UPDATE {
-        if last_update.elapsed() < TOOLTIP_UPDATE_COOLDOWN {
+    let taskbars = find_taskbar_rects();
+    if taskbars.is_empty() {
+        // No taskbar window found (Explorer restarting?) - ask the shell where it keeps the taskbar
+        let Some((edge, taskbar)) = appbar::get_taskbar() else {
+            debug_logf("No taskbar found - HWND {0} is not a clock tooltip", &[&(hwnd as usize)]);
             return false;
-        }
+        };
+        let is_in_taskbar = is_near_taskbar(&taskbar, &rect);
+        debug_logf("No taskbar windows found - HWND {0}: rect({1},{2},{3},{4}), appbar edge={5}, in_taskbar={6}",
+                  &[&(hwnd as usize), &rect.left, &rect.top, &rect.right, &rect.bottom, &format!("{:?}", edge), &is_in_taskbar]);
+        return is_in_taskbar;
     }
-    true
+
+    let auto_hide = is_taskbar_auto_hide();
+    let taskbars: Vec<RECT> = taskbars
+        .into_iter()
+        .filter(|(taskbar, rect)| is_taskbar_showing(*taskbar, rect, auto_hide))
+        .map(|(_, rect)| rect)
+        .collect();
+    let is_in_taskbar = taskbars.iter().any(|taskbar| is_near_taskbar(taskbar, &rect));
+    debug_logf("Tooltip position check - HWND {0}: rect({1},{2},{3},{4}), taskbars={5}, in_taskbar={6}", 
+              &[&(hwnd as usize), &rect.left, &rect.top, &rect.right, &rect.bottom, &taskbars.len(), &is_in_taskbar]);
+    is_in_taskbar
 }
 
-unsafe fn mark_tooltip_updated() {
-    LAST_TOOLTIP_UPDATE = Some(Instant::now());
+// If the tooltip still shows text we wrote, returns the native text it was built from so the
+// extra lines are regenerated rather than appended a second time
+unsafe fn strip_previous_augmentation(hwnd: HWND, current_text: String) -> String {
+    let augmented = AUGMENTED_TOOLTIPS.lock().unwrap_or_else(|e| e.into_inner());
+    match augmented.iter().find(|(tooltip, _, written)| *tooltip == hwnd as usize && *written == current_text) {
+        Some((_, original, _)) => {
+            debug_logf("Tooltip HWND {0} already extended - rebuilding from original text", &[&(hwnd as usize)]);
+            original.clone()
+        }
+        None => current_text,
+    }
 }
 
-fn get_uptime() -> String {
-    unsafe {
-        let tick_count = GetTickCount();
-        let uptime_seconds = tick_count / 1000;
-        let days = uptime_seconds / (24 * 3600);
-        let hours = (uptime_seconds % (24 * 3600)) / 3600;
-        let minutes = (uptime_seconds % 3600) / 60;
+unsafe fn remember_augmentation(hwnd: HWND, original: &str, written: &str) {
+    let mut augmented = AUGMENTED_TOOLTIPS.lock().unwrap_or_else(|e| e.into_inner());
+    augmented.retain(|(tooltip, _, _)| *tooltip != hwnd as usize && IsWindow(*tooltip as HWND) != 0);
+    augmented.push((hwnd as usize, original.to_string(), written.to_string()));
+}
 
-        if days > 0 {
-            format!("{}d {}h {}m", days, hours, minutes)
-        } else if hours > 0 {
-            format!("{}h {}m", hours, minutes)
-        } else {
-            format!("{}m", minutes)
-        }
+fn tooltip_update_cooldown_ms() -> u64 {
+    match settings::get().map(|settings| settings.cooldown_ms.load(Ordering::Relaxed)) {
+        Some(stored) if stored != 0 => u64::from(stored - 1),
+        _ => DEFAULT_TOOLTIP_UPDATE_COOLDOWN_MS,
     }
 }
 
-fn get_norwegian_week() -> String {
-    let now = chrono::Local::now();
-    let naive_date = now.date_naive();
-    let iso_week = naive_date.iso_week();
-    format!("Uke {}", iso_week.week())
+fn should_update_tooltip() -> bool {
+    let last_update = LAST_TOOLTIP_UPDATE_MS.load(Ordering::SeqCst);
+    last_update == 0 || unsafe { GetTickCount64() }.saturating_sub(last_update) >= tooltip_update_cooldown_ms()
+}
+
+fn mark_tooltip_updated() {
+    LAST_TOOLTIP_UPDATE_MS.store(unsafe { GetTickCount64() }, Ordering::SeqCst);
+}
+
+fn is_suppressed() -> bool {
+    settings::get().is_some_and(|settings| settings.suppressed.load(Ordering::Relaxed) != 0)
+}
+
+// True when no activation key is configured or the configured one is held down
+fn is_activation_key_held() -> bool {
+    match settings::get().map(|settings| settings.activation_key.load(Ordering::Relaxed)) {
+        Some(vk) if vk != 0 => {
+            // The most significant bit is set while the key is down
+            let state = unsafe { GetAsyncKeyState(vk as i32) };
+            state < 0
+        }
+        _ => true,
+    }
 }
 
 unsafe fn modify_tooltip_text(hwnd: HWND) {
     debug_logf("modify_tooltip_text called for HWND {0}", &[&(hwnd as usize)]);
     
+    if is_suppressed() {
+        debug_log("Tooltip modification skipped - paused");
+        return;
+    }
+
+    if !is_activation_key_held() {
+        debug_log("Tooltip modification skipped - activation key not held");
+        return;
+    }
+
     if !should_update_tooltip() {
         debug_log("Tooltip modification skipped due to cooldown");
         return;
@@ -158,7 +344,7 @@ unsafe fn modify_tooltip_text(hwnd: HWND) {
     }
     debug_log("Tooltip is in taskbar area");
 
-    let current_text = get_window_text(hwnd);
+    let current_text = strip_previous_augmentation(hwnd, get_window_text(hwnd));
     
     // Only modify if it looks like a time/date tooltip
     let has_time_markers = current_text.contains(":") || 
@@ -173,20 +359,25 @@ unsafe fn modify_tooltip_text(hwnd: HWND) {
     }
     debug_logf("Confirmed time/date tooltip with text: '{0}'", &[&current_text]);
 
-    let uptime = get_uptime();
-    let week = get_norwegian_week();
-    let new_text = format!("{}\nOpptid: {}\n{}", current_text, uptime, week);
+    let extra_text = sections::generate_tooltip_text();
+    if extra_text.is_empty() {
+        debug_log("No tooltip sections enabled - leaving tooltip unchanged");
+        return;
+    }
+    let new_text = format!("{}\n{}", current_text, extra_text);
     debug_logf("Generated new tooltip text: '{0}'", &[&new_text]);
     
-    let new_text_utf16 = string_to_utf16(&new_text);
+    let new_text_utf16 = to_wide(&new_text);
 
     // Try different approaches based on the window class
     let mut success = false;
     
     if class_name == "tooltips_class32" {
-        // Traditional tooltip - use SetWindowTextW
-        let result = SetWindowTextW(hwnd, new_text_utf16.as_ptr());
-        if result != 0 {
+        // Traditional tooltip - cap its width so long lines wrap, then replace the window text
+        let mut previous_width: usize = 0;
+        SendMessageTimeoutW(hwnd, TTM_SETMAXTIPWIDTH, 0, max_tip_width() as LPARAM,
+                            SMTO_ABORTIFHUNG, TEXT_MESSAGE_TIMEOUT_MS, &mut previous_width);
+        if set_window_text(hwnd, new_text_utf16.as_ptr()) {
             debug_log("Successfully updated traditional tooltip text");
             success = true;
         } else {
@@ -195,24 +386,22 @@ unsafe fn modify_tooltip_text(hwnd: HWND) {
     } else if class_name == "Xaml_WindowedPopupClass" {
         // XAML tooltip - try multiple approaches
         
-        // Method 1: Try SetWindowTextW first
-        let result = SetWindowTextW(hwnd, new_text_utf16.as_ptr());
-        if result != 0 {
-            debug_log("Successfully updated XAML tooltip text with SetWindowTextW");
+        // Method 1: Try setting the popup's own text first
+        if set_window_text(hwnd, new_text_utf16.as_ptr()) {
+            debug_log("Successfully updated XAML tooltip text with WM_SETTEXT");
             success = true;
         } else {
-            debug_log("SetWindowTextW failed for XAML tooltip, trying child window approach");
+            debug_log("WM_SETTEXT failed for XAML tooltip, trying child window approach");
             
             // Method 2: Try to find and update child windows
             unsafe extern "system" fn enum_child_proc(child_hwnd: HWND, lparam: LPARAM) -> BOOL {
-                let new_text_ptr = lparam as *const Vec<u16>;
-                let new_text = &*new_text_ptr;
+                let new_text = lparam as *const u16;
                 
                 let child_class = {
                     let mut class_name = [0u16; 256];
                     let len = GetClassNameW(child_hwnd, class_name.as_mut_ptr(), class_name.len() as i32);
                     if len > 0 {
-                        utf16_to_string(&class_name[..len as usize])
+                        from_wide(&class_name[..len as usize])
                     } else {
                         String::new()
                     }
@@ -221,11 +410,8 @@ unsafe fn modify_tooltip_text(hwnd: HWND) {
                 debug_logf("Found child window class: {0}", &[&child_class]);
                 
                 // Try to update any text-containing child windows
-                if !child_class.is_empty() {
-                    let result = SetWindowTextW(child_hwnd, new_text.as_ptr());
-                    if result != 0 {
-                        debug_logf("Successfully updated child window text for class: {0}", &[&child_class]);
-                    }
+                if !child_class.is_empty() && set_window_text(child_hwnd, new_text) {
+                    debug_logf("Successfully updated child window text for class: {0}", &[&child_class]);
                 }
                 
                 1 // Continue enumeration
@@ -238,6 +424,7 @@ unsafe fn modify_tooltip_text(hwnd: HWND) {
     
     if success {
         mark_tooltip_updated();
+        remember_augmentation(hwnd, &current_text, &new_text);
         
         // Force redraw
         InvalidateRect(hwnd, ptr::null(), 1);
@@ -246,6 +433,97 @@ unsafe fn modify_tooltip_text(hwnd: HWND) {
     }
 }
 
+// Hands a newly created tooltip to this process's worker thread, starting one if needed
+unsafe fn schedule_tooltip_update(hwnd: HWND) {
+    let mut worker = TOOLTIP_WORKER.lock().unwrap_or_else(|e| e.into_inner());
+    let job = (hwnd as usize, Instant::now() + TOOLTIP_SETTLE_DELAY);
+    let job = match worker.as_ref() {
+        Some(sender) => match sender.send(job) {
+            Ok(()) => return,
+            Err(mpsc::SendError(job)) => job,
+        },
+        None => job,
+    };
+
+    let (sender, receiver) = mpsc::channel();
+    let _ = sender.send(job);
+    if spawn_tooltip_worker(receiver) {
+        *worker = Some(sender);
+    } else {
+        *worker = None;
+    }
+}
+
+// Starts the worker holding its own reference to this DLL, so unhooking or FreeLibrary can't unload
+// the code under it. The worker drops the reference as it exits
+unsafe fn spawn_tooltip_worker(receiver: mpsc::Receiver<(usize, Instant)>) -> bool {
+    let mut module: HMODULE = ptr::null_mut();
+    let address = tooltip_worker_main as *const u16;
+    if GetModuleHandleExW(GET_MODULE_HANDLE_EX_FLAG_FROM_ADDRESS, address, &mut module) == 0 {
+        debug_logf("Failed to pin the DLL for the tooltip worker, error code: {0}", &[&GetLastError()]);
+        return false;
+    }
+
+    let start = Box::into_raw(Box::new((module, receiver)));
+    let thread = CreateThread(ptr::null_mut(), 0, Some(tooltip_worker_main), start as LPVOID, 0, ptr::null_mut());
+    if thread.is_null() {
+        debug_logf("Failed to start the tooltip worker, error code: {0}", &[&GetLastError()]);
+        drop(Box::from_raw(start));
+        FreeLibrary(module);
+        return false;
+    }
+    CloseHandle(thread);
+    true
+}
+
+unsafe extern "system" fn tooltip_worker_main(start: LPVOID) -> DWORD {
+    let (module, receiver) = *Box::from_raw(start as *mut (HMODULE, mpsc::Receiver<(usize, Instant)>));
+    tooltip_worker(receiver);
+    // Returning would run code in the DLL this may be unloading, so release it and exit in one call
+    FreeLibraryAndExitThread(module, 0);
+    0
+}
+
+// Drops the worker's channel so it exits instead of taking more tooltips. try_lock because on
+// process exit a thread that held the lock may have been terminated with it
+fn stop_tooltip_worker() {
+    if let Ok(mut worker) = TOOLTIP_WORKER.try_lock() {
+        *worker = None;
+    }
+}
+
+fn tooltip_worker(receiver: mpsc::Receiver<(usize, Instant)>) {
+    unsafe { debug_log("Tooltip worker started") };
+    loop {
+        let (hwnd_value, due) = match receiver.recv_timeout(TOOLTIP_WORKER_IDLE) {
+            Ok(job) => job,
+            Err(_) => {
+                // Check again under the lock so a tooltip queued just now isn't lost
+                let mut worker = TOOLTIP_WORKER.lock().unwrap_or_else(|e| e.into_inner());
+                match receiver.try_recv() {
+                    Ok(job) => job,
+                    Err(_) => {
+                        *worker = None;
+                        unsafe { debug_log("Tooltip worker idle - exiting") };
+                        return;
+                    }
+                }
+            }
+        };
+
+        std::thread::sleep(due.saturating_duration_since(Instant::now()));
+        unsafe {
+            let hwnd = hwnd_value as HWND;
+            if IsWindow(hwnd) != 0 {
+                debug_logf("Window still valid, proceeding with modification for HWND {0}", &[&hwnd_value]);
+                modify_tooltip_text(hwnd);
+            } else {
+                debug_logf("Window no longer valid for HWND {0}", &[&hwnd_value]);
+            }
+        }
+    }
+}
+
 // CBT hook procedure - this will be called in each process
 unsafe extern "system" fn cbt_hook_proc(
     code: i32,
@@ -253,7 +531,7 @@ unsafe extern "system" fn cbt_hook_proc(
     lparam: LPARAM,
 ) -> LRESULT {
     // Only log for window creation events to reduce noise
-    if code == HCBT_CREATEWND {
+    if code == HCBT_CREATEWND && !is_suppressed() {
         let hwnd = wparam as HWND;
         debug_logf("CBT Hook - Window created: HWND {0}", &[&(hwnd as usize)]);
         
@@ -262,30 +540,15 @@ unsafe extern "system" fn cbt_hook_proc(
         if class_name == "tooltips_class32" || class_name == "Xaml_WindowedPopupClass" {
             debug_logf("Found tooltip window creation: HWND {0}, class: {1}", &[&(hwnd as usize), &class_name]);
             
-            // Schedule tooltip modification after a short delay
-            let hwnd_value = hwnd as usize;
-            std::thread::spawn(move || {
-                debug_logf("Starting delayed tooltip modification for HWND {0}", &[&hwnd_value]);
-                std::thread::sleep(std::time::Duration::from_millis(100));
-                
-                unsafe {
-                    let hwnd = hwnd_value as HWND;
-                    if IsWindow(hwnd) != 0 {
-                        debug_logf("Window still valid, proceeding with modification for HWND {0}", &[&hwnd_value]);
-                        modify_tooltip_text(hwnd);
-                    } else {
-                        debug_logf("Window no longer valid for HWND {0}", &[&hwnd_value]);
-                    }
-                }
-            });
+            // Modify it once it has settled, off the hook thread
+            schedule_tooltip_update(hwnd);
         }else {
             debug_logf("Not a tooltip window, class: '{0}'", &[&class_name]);
         }
     } else if code >= 0 {
         // Log other hook codes at a lower frequency
-        static mut HOOK_CALL_COUNT: u32 = 0;
-        HOOK_CALL_COUNT += 1;
-        if HOOK_CALL_COUNT % 100 == 0 {
+        static HOOK_CALL_COUNT: AtomicU32 = AtomicU32::new(0);
+        if (HOOK_CALL_COUNT.fetch_add(1, Ordering::Relaxed) + 1).is_multiple_of(100) {
             debug_logf("CBT Hook called 100 times, latest code: {0}", &[&code]);
         }
     }
@@ -297,32 +560,52 @@ unsafe extern "system" fn cbt_hook_proc(
 #[no_mangle]
 pub unsafe extern "system" fn InstallHook() -> BOOL {
     debug_log("InstallHook called");
-    
+    install_hook(0) // Global hook
+}
+
+// Hooks only the given GUI thread, e.g. Explorer's taskbar thread, instead of every process
+#[no_mangle]
+pub unsafe extern "system" fn InstallHookForThread(thread_id: u32) -> BOOL {
+    debug_logf("InstallHookForThread called for thread {0}", &[&thread_id]);
+    install_hook(thread_id)
+}
+
+unsafe fn install_hook(thread_id: DWORD) -> BOOL {
     if HOOK_INSTALLED.load(Ordering::SeqCst) {
         debug_log("Hook already installed");
         return 1; // Already installed
     }
 
-    debug_logf("Installing CBT hook with DLL instance: {0}", &[&(DLL_INSTANCE as usize)]);
+    let dll_instance = DLL_INSTANCE.load(Ordering::SeqCst);
+    debug_logf("Installing CBT hook with DLL instance: {0}", &[&(dll_instance as usize)]);
     let hook = SetWindowsHookExW(
         WH_CBT,
         Some(cbt_hook_proc),
-        DLL_INSTANCE,  // Use the DLL instance instead of null
-        0, // Global hook
+        dll_instance,  // Use the DLL instance instead of null
+        thread_id,
     );
     
     if !hook.is_null() {
         HOOK_HANDLE.store(hook, Ordering::SeqCst);
         HOOK_INSTALLED.store(true, Ordering::SeqCst);
+        LAST_INSTALL_ERROR.store(0, Ordering::SeqCst);
         debug_logf("Hook installed successfully with handle: {0}", &[&(hook as usize)]);
         1 // Success
     } else {
         let error = GetLastError();
+        LAST_INSTALL_ERROR.store(error, Ordering::SeqCst);
         debug_logf("Failed to install hook, error code: {0}", &[&error]);
         0 // Failure
     }
 }
 
+// Error code of the last failed InstallHook/InstallHookForThread, 0 if the last install succeeded.
+// Read this rather than GetLastError, which the debug logging may have overwritten by then
+#[no_mangle]
+pub unsafe extern "system" fn GetLastInstallError() -> DWORD {
+    LAST_INSTALL_ERROR.load(Ordering::SeqCst)
+}
+
 #[no_mangle]
 pub unsafe extern "system" fn UninstallHook() -> BOOL {
     debug_log("UninstallHook called");
@@ -352,6 +635,168 @@ pub unsafe extern "system" fn UninstallHook() -> BOOL {
     }
 }
 
+// Applies an exported setter to the shared settings seen by every process
+unsafe fn update_settings(setter: &str, update: impl FnOnce(&settings::SharedSettings)) -> BOOL {
+    match settings::get() {
+        Some(settings) => {
+            update(settings);
+            1
+        }
+        None => {
+            debug_logf("{0} called without a settings mapping", &[&setter]);
+            0
+        }
+    }
+}
+
+// Copies the lines the hook would append right now into `buffer` (NUL-terminated, truncated to
+// `capacity`) and returns the full length in UTF-16 units so the caller can retry with more room
+#[no_mangle]
+pub unsafe extern "system" fn GenerateTooltipText(buffer: *mut u16, capacity: u32) -> u32 {
+    let text: Vec<u16> = sections::generate_tooltip_text().encode_utf16().collect();
+    if !buffer.is_null() && capacity > 0 {
+        let copied = text.len().min(capacity as usize - 1);
+        ptr::copy_nonoverlapping(text.as_ptr(), buffer, copied);
+        *buffer.add(copied) = 0;
+    }
+    text.len() as u32
+}
+
+// Replaces the built-in tooltip lines in every process; null or empty restores them
+#[no_mangle]
+pub unsafe extern "system" fn SetCustomText(text: *const u16) -> BOOL {
+    update_settings("SetCustomText", |settings| settings.custom_text.store(&utf16_ptr_to_string(text)))
+}
+
+// Sets the comma-separated order of built-in tooltip sections; null or empty restores the default.
+// Unknown names add no line, so the host sends "none" to turn every section off
+#[no_mangle]
+pub unsafe extern "system" fn SetSectionOrder(order: *const u16) -> BOOL {
+    update_settings("SetSectionOrder", |settings| settings.section_order.store(&utf16_ptr_to_string(order)))
+}
+
+// Sets the week line format, with {week} and {year} placeholders; null or empty restores the default
+#[no_mangle]
+pub unsafe extern "system" fn SetWeekLabelFormat(format: *const u16) -> BOOL {
+    update_settings("SetWeekLabelFormat", |settings| settings.week_label_format.store(&utf16_ptr_to_string(format)))
+}
+
+// Sets the strftime-style date/time line format; null or empty restores the user's locale format
+#[no_mangle]
+pub unsafe extern "system" fn SetDateTimeFormat(format: *const u16) -> BOOL {
+    update_settings("SetDateTimeFormat", |settings| settings.datetime_format.store(&utf16_ptr_to_string(format)))
+}
+
+// Sets the secondary timezones as "<offset hours>\t<label>" lines; null or empty clears them
+#[no_mangle]
+pub unsafe extern "system" fn SetTimezones(zones: *const u16) -> BOOL {
+    update_settings("SetTimezones", |settings| settings.timezones.store(&utf16_ptr_to_string(zones)))
+}
+
+// Sets the line prefixes as "<section>\t<marker>" lines; null or empty turns them off
+#[no_mangle]
+pub unsafe extern "system" fn SetDecorations(decorations: *const u16) -> BOOL {
+    update_settings("SetDecorations", |settings| settings.decorations.store(&utf16_ptr_to_string(decorations)))
+}
+
+// Sets the location for the sunrise/sunset line as "<latitude>,<longitude>"; null or empty clears it
+#[no_mangle]
+pub unsafe extern "system" fn SetLocation(location: *const u16) -> BOOL {
+    update_settings("SetLocation", |settings| settings.location.store(&utf16_ptr_to_string(location)))
+}
+
+// Sets the countdown as "<target unix timestamp>\t<label>"; null or empty clears it
+#[no_mangle]
+pub unsafe extern "system" fn SetCountdown(countdown: *const u16) -> BOOL {
+    update_settings("SetCountdown", |settings| settings.countdown.store(&utf16_ptr_to_string(countdown)))
+}
+
+// Non-zero shows times in 12-hour format with AM/PM, zero in 24-hour format (the default)
+#[no_mangle]
+pub unsafe extern "system" fn SetTime12h(enabled: u32) -> BOOL {
+    update_settings("SetTime12h", |settings| settings.time_12h.store(enabled, Ordering::Relaxed))
+}
+
+// Selects the uptime precision: 0 for minutes (the default), 1 to include seconds
+#[no_mangle]
+pub unsafe extern "system" fn SetUptimePrecision(precision: u32) -> BOOL {
+    update_settings("SetUptimePrecision", |settings| settings.uptime_precision.store(precision, Ordering::Relaxed))
+}
+
+// Selects the week numbering: 0 for ISO-8601, 1 for US (Sunday start, week 1 contains January 1)
+#[no_mangle]
+pub unsafe extern "system" fn SetWeekScheme(scheme: u32) -> BOOL {
+    update_settings("SetWeekScheme", |settings| settings.week_scheme.store(scheme, Ordering::Relaxed))
+}
+
+// Sets the minimum time between two tooltip rewrites in the same process (default 500ms);
+// u32::MAX restores the default
+#[no_mangle]
+pub unsafe extern "system" fn SetCooldownMs(ms: u32) -> BOOL {
+    update_settings("SetCooldownMs", |settings| settings.cooldown_ms.store(stored_or_unset(ms), Ordering::Relaxed))
+}
+
+// Caps the width of classic tooltips in pixels so long lines wrap (default 400); 0 removes the cap
+// and u32::MAX restores the default
+#[no_mangle]
+pub unsafe extern "system" fn SetMaxTipWidth(px: u32) -> BOOL {
+    update_settings("SetMaxTipWidth", |settings| settings.max_tip_width.store(stored_or_unset(px), Ordering::Relaxed))
+}
+
+// Shared value for the plus-one encoded settings, where 0 means unset
+fn stored_or_unset(value: u32) -> u32 {
+    if value == u32::MAX { 0 } else { value + 1 }
+}
+
+// Only extends the tooltip while the given virtual key (e.g. VK_SHIFT) is held; 0 always extends it
+#[no_mangle]
+pub unsafe extern "system" fn SetActivationKey(vk: u32) -> BOOL {
+    update_settings("SetActivationKey", |settings| settings.activation_key.store(vk, Ordering::Relaxed))
+}
+
+// Sets the text between section lines, e.g. " | " for a single row; null or empty restores the newline
+#[no_mangle]
+pub unsafe extern "system" fn SetLineSeparator(separator: *const u16) -> BOOL {
+    update_settings("SetLineSeparator", |settings| settings.line_separator.store(&utf16_ptr_to_string(separator)))
+}
+
+// Pauses (non-zero) or resumes (zero) tooltip rewriting without removing the hook
+#[no_mangle]
+pub unsafe extern "system" fn SetSuppressed(suppressed: u32) -> BOOL {
+    update_settings("SetSuppressed", |settings| settings.suppressed.store(suppressed, Ordering::Relaxed))
+}
+
+// Polling alternative to the CBT hook, for systems where SetWindowsHookExW is blocked.
+// Runs in the calling process; each tooltip is modified once per appearance.
+#[no_mangle]
+pub unsafe extern "system" fn PollTooltips() -> BOOL {
+    let mut visible = Vec::new();
+    for class in TOOLTIP_CLASSES {
+        let class_utf16 = to_wide(class);
+        let mut hwnd = FindWindowExW(ptr::null_mut(), ptr::null_mut(), class_utf16.as_ptr(), ptr::null());
+        while !hwnd.is_null() {
+            if IsWindowVisible(hwnd) != 0 {
+                visible.push(hwnd as usize);
+            }
+            hwnd = FindWindowExW(ptr::null_mut(), hwnd, class_utf16.as_ptr(), ptr::null());
+        }
+    }
+
+    let newly_visible: Vec<usize> = {
+        let mut polled = POLLED_TOOLTIPS.lock().unwrap_or_else(|e| e.into_inner());
+        let newly_visible = visible.iter().filter(|hwnd| !polled.contains(hwnd)).copied().collect();
+        *polled = visible;
+        newly_visible
+    };
+
+    for hwnd_value in newly_visible {
+        debug_logf("Poll found newly visible tooltip HWND {0}", &[&hwnd_value]);
+        modify_tooltip_text(hwnd_value as HWND);
+    }
+
+    1
+}
+
 // DLL entry point
 #[no_mangle]
 pub unsafe extern "system" fn DllMain(
@@ -362,13 +807,18 @@ pub unsafe extern "system" fn DllMain(
     match fdw_reason {
         DLL_PROCESS_ATTACH => {
             // Store the DLL instance for the hook
-            DLL_INSTANCE = hinst_dll;
+            DLL_INSTANCE.store(hinst_dll, Ordering::SeqCst);
             debug_logf("DLL attached to process, instance: {0}", &[&(hinst_dll as usize)]);
+            settings::open();
             1
         }
         DLL_PROCESS_DETACH => {
             // Cleanup when DLL is unloaded from a process
             debug_log("DLL detaching from process");
+            // The worker holds a reference to the DLL, so it has already exited unless the process is
+            // terminating; either way nothing may read the settings once they're unmapped
+            stop_tooltip_worker();
+            settings::close();
             1
         }
         _ => {
@@ -0,0 +1,728 @@
+// Built-in tooltip sections appended below the native clock text.
+
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Datelike, FixedOffset, NaiveDate, NaiveTime, Offset, Timelike};
+use std::fmt::Write;
+use std::ptr;
+use std::sync::atomic::Ordering;
+use std::sync::Mutex;
+use winapi::shared::minwindef::FILETIME;
+use winapi::um::datetimeapi::{GetDateFormatEx, GetTimeFormatEx};
+use winapi::um::errhandlingapi::GetLastError;
+use winapi::um::minwinbase::SYSTEMTIME;
+use winapi::um::processthreadsapi::GetSystemTimes;
+use winapi::um::sysinfoapi::{GlobalMemoryStatusEx, MEMORYSTATUSEX};
+use winapi::um::winbase::{GetSystemPowerStatus, SYSTEM_POWER_STATUS};
+use winapi::um::wininet::{
+    InternetGetConnectedState, INTERNET_CONNECTION_LAN, INTERNET_CONNECTION_MODEM, INTERNET_CONNECTION_OFFLINE,
+    INTERNET_CONNECTION_PROXY,
+};
+
+use xclock_util::wide::{from_wide, to_wide};
+
+use crate::time::{SystemTime, TimeSource};
+use crate::{debug_logf, settings};
+
+const DEFAULT_SECTION_ORDER: &str = "uptime,week,datetime";
+const DEFAULT_LINE_SEPARATOR: &str = "\n";
+const DEFAULT_WEEK_LABEL_FORMAT: &str = "Uke {week}";
+// Used when the user's locale can't format the date/time line; chrono's day and month names are English
+const FALLBACK_DATETIME_FORMAT: &str = "%A %d %B %Y %H:%M";
+const FALLBACK_DATETIME_FORMAT_12H: &str = "%A %d %B %Y %I:%M %p";
+// GetDateFormatEx/GetTimeFormatEx flags, not exported by winapi 0.3
+const DATE_LONGDATE: u32 = 0x2;
+const TIME_NOSECONDS: u32 = 0x2;
+const TIME_NOTIMEMARKER: u32 = 0x4;
+const TIME_FORCE24HOURFORMAT: u32 = 0x8;
+// GetTimeFormatEx picture for 12-hour times, matching TIME_FORMAT_12H
+const LOCALE_TIME_FORMAT_12H: &str = "hh:mm tt";
+const LOCALE_TEXT_BUFFER: usize = 128;
+const TIME_FORMAT: &str = "%H:%M";
+const TIME_FORMAT_12H: &str = "%I:%M %p";
+
+// Values of SharedSettings::week_scheme
+const WEEK_SCHEME_ISO: u32 = 0;
+const WEEK_SCHEME_US_SUNDAY_START: u32 = 1;
+
+// Values of SharedSettings::uptime_precision
+const UPTIME_PRECISION_MINUTES: u32 = 0;
+const UPTIME_PRECISION_SECONDS: u32 = 1;
+
+// SYSTEM_POWER_STATUS flag values
+const BATTERY_FLAG_CHARGING: u8 = 8;
+const BATTERY_FLAG_NO_BATTERY: u8 = 128;
+const BATTERY_PERCENT_UNKNOWN: u8 = 255;
+const AC_LINE_ONLINE: u8 = 1;
+
+// (idle, kernel + user) times from the previous GetSystemTimes call in this process
+static LAST_CPU_SAMPLE: Mutex<Option<(u64, u64)>> = Mutex::new(None);
+
+// "2d 4h 13m", leaving out leading zero units
+fn format_duration(total_seconds: u64) -> String {
+    let days = total_seconds / (24 * 3600);
+    let hours = (total_seconds % (24 * 3600)) / 3600;
+    let minutes = (total_seconds % 3600) / 60;
+
+    if days > 0 {
+        format!("{}d {}h {}m", days, hours, minutes)
+    } else if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else {
+        format!("{}m", minutes)
+    }
+}
+
+// Like format_duration with seconds appended, e.g. "1h 2m 5s"; just "5s" during the first minute
+fn format_duration_with_seconds(total_seconds: u64) -> String {
+    let seconds = total_seconds % 60;
+    if total_seconds < 60 {
+        format!("{}s", seconds)
+    } else {
+        format!("{} {}s", format_duration(total_seconds), seconds)
+    }
+}
+
+// Milliseconds since boot as "Xd Yh Zm", with seconds appended at UPTIME_PRECISION_SECONDS
+fn format_uptime(tick_count_ms: u64, precision: u32) -> String {
+    if precision == UPTIME_PRECISION_SECONDS {
+        format_duration_with_seconds(tick_count_ms / 1000)
+    } else {
+        format_duration(tick_count_ms / 1000)
+    }
+}
+
+fn get_uptime(time: &dyn TimeSource) -> String {
+    let tick_count = time.uptime_ms();
+    let precision = settings::get()
+        .map_or(UPTIME_PRECISION_MINUTES, |settings| settings.uptime_precision.load(Ordering::Relaxed));
+    format_uptime(tick_count, precision)
+}
+
+// "Deadline: 2d 4h 13m" until the target time, "Deadline: passed" after it
+fn get_countdown(time: &dyn TimeSource) -> Option<String> {
+    let countdown = settings::get()?.countdown.load()?;
+    let (target, label) = countdown.split_once('\t')?;
+    let remaining = target.parse::<i64>().ok()? - time.now().time.timestamp();
+    if remaining <= 0 {
+        Some(format!("{}: passed", label))
+    } else {
+        Some(format!("{}: {}", label, format_duration(remaining as u64)))
+    }
+}
+
+// (week, week-based year) under the configured numbering scheme
+fn week_number(date: NaiveDate, scheme: u32) -> (u32, i32) {
+    if scheme == WEEK_SCHEME_US_SUNDAY_START {
+        // Weeks start on Sunday and week 1 is the one containing January 1
+        let jan1_offset = NaiveDate::from_yo_opt(date.year(), 1)
+            .map_or(0, |jan1| jan1.weekday().num_days_from_sunday());
+        return ((date.ordinal0() + jan1_offset) / 7 + 1, date.year());
+    }
+
+    let iso_week = date.iso_week();
+    (iso_week.week(), iso_week.year())
+}
+
+fn get_norwegian_week(time: &dyn TimeSource) -> String {
+    let now = time.now();
+    let naive_date = now.time.date_naive();
+    let scheme = settings::get().map_or(WEEK_SCHEME_ISO, |settings| settings.week_scheme.load(Ordering::Relaxed));
+    let (week, year) = week_number(naive_date, scheme);
+    let label_format = settings::get()
+        .and_then(|settings| settings.week_label_format.load())
+        .unwrap_or_else(|| DEFAULT_WEEK_LABEL_FORMAT.to_string());
+    let label = label_format
+        .replace("{week}", &week.to_string())
+        .replace("{year}", &year.to_string());
+    label + now.utc_suffix()
+}
+
+fn uses_12h_clock() -> bool {
+    settings::get().is_some_and(|settings| settings.time_12h.load(Ordering::Relaxed) != 0)
+}
+
+// Hours and minutes for the secondary timezone and sunrise/sunset lines
+fn time_format() -> &'static str {
+    if uses_12h_clock() { TIME_FORMAT_12H } else { TIME_FORMAT }
+}
+
+fn get_datetime(time: &dyn TimeSource) -> String {
+    let now = time.now();
+    format_datetime(&now.time) + now.utc_suffix()
+}
+
+fn format_datetime(now: &DateTime<FixedOffset>) -> String {
+    // An explicit format wins over the user's locale and the 12/24-hour choice
+    let Some(datetime_format) = settings::get().and_then(|settings| settings.datetime_format.load()) else {
+        return default_datetime(now);
+    };
+
+    // chrono panics when displaying an invalid format, so check it up front
+    if StrftimeItems::new(&datetime_format).any(|item| matches!(item, Item::Error)) {
+        unsafe { debug_logf("Invalid datetime format '{0}', using default", &[&datetime_format]) };
+        return default_datetime(now);
+    }
+
+    let mut text = String::new();
+    if write!(text, "{}", now.format(&datetime_format)).is_err() {
+        unsafe { debug_logf("Failed to format datetime with '{0}', using default", &[&datetime_format]) };
+        return default_datetime(now);
+    }
+    text
+}
+
+// The long date and the time as the user's locale writes them, e.g. "onsdag 1. januar 2025 00:30"
+fn default_datetime(now: &DateTime<FixedOffset>) -> String {
+    locale_datetime(now).unwrap_or_else(|| {
+        let fallback_format = if uses_12h_clock() { FALLBACK_DATETIME_FORMAT_12H } else { FALLBACK_DATETIME_FORMAT };
+        now.format(fallback_format).to_string()
+    })
+}
+
+fn locale_datetime(now: &DateTime<FixedOffset>) -> Option<String> {
+    let time = SYSTEMTIME {
+        wYear: now.year() as u16,
+        wMonth: now.month() as u16,
+        wDayOfWeek: now.weekday().num_days_from_sunday() as u16,
+        wDay: now.day() as u16,
+        wHour: now.hour() as u16,
+        wMinute: now.minute() as u16,
+        wSecond: now.second() as u16,
+        wMilliseconds: 0,
+    };
+    let mut date = [0u16; LOCALE_TEXT_BUFFER];
+    // A null locale name is LOCALE_NAME_USER_DEFAULT
+    let date_len = unsafe {
+        let (buffer, size) = (date.as_mut_ptr(), date.len() as i32);
+        GetDateFormatEx(ptr::null(), DATE_LONGDATE, &time, ptr::null(), buffer, size, ptr::null())
+    };
+
+    let format_12h = to_wide(LOCALE_TIME_FORMAT_12H);
+    let (flags, format) = if uses_12h_clock() {
+        (0, format_12h.as_ptr())
+    } else {
+        (TIME_NOSECONDS | TIME_NOTIMEMARKER | TIME_FORCE24HOURFORMAT, ptr::null())
+    };
+    let mut clock = [0u16; LOCALE_TEXT_BUFFER];
+    let clock_len = unsafe {
+        let (buffer, size) = (clock.as_mut_ptr(), clock.len() as i32);
+        GetTimeFormatEx(ptr::null(), flags, &time, format, buffer, size)
+    };
+
+    if date_len <= 0 || clock_len <= 0 {
+        unsafe { debug_logf("Locale date/time formatting failed, error code: {0}", &[&GetLastError()]) };
+        return None;
+    }
+    // Both lengths include the terminating null
+    Some(format!("{} {}", from_wide(&date[..date_len as usize - 1]), from_wide(&clock[..clock_len as usize - 1])))
+}
+
+// None on machines without a battery, so the line is left out entirely
+fn get_battery() -> Option<String> {
+    let mut status: SYSTEM_POWER_STATUS = unsafe { std::mem::zeroed() };
+    if unsafe { GetSystemPowerStatus(&mut status) } == 0 {
+        return None;
+    }
+    if status.BatteryFlag == BATTERY_FLAG_NO_BATTERY || status.BatteryLifePercent == BATTERY_PERCENT_UNKNOWN {
+        return None;
+    }
+
+    let state = if status.BatteryFlag & BATTERY_FLAG_CHARGING != 0 {
+        " (charging)"
+    } else if status.ACLineStatus == AC_LINE_ONLINE {
+        " (plugged in)"
+    } else {
+        ""
+    };
+    Some(format!("Battery: {}%{}", status.BatteryLifePercent, state))
+}
+
+// Local connection state only - no DNS lookups or requests, so it never delays the tooltip
+fn get_network_status() -> String {
+    let mut flags = 0;
+    let connected = unsafe { InternetGetConnectedState(&mut flags, 0) } != 0;
+    if !connected || flags & INTERNET_CONNECTION_OFFLINE != 0 {
+        return "Network: Offline".to_string();
+    }
+
+    // WinINet reports Wi-Fi and Ethernet alike as LAN
+    let kind = if flags & INTERNET_CONNECTION_LAN != 0 {
+        " (LAN)"
+    } else if flags & INTERNET_CONNECTION_MODEM != 0 {
+        " (modem)"
+    } else if flags & INTERNET_CONNECTION_PROXY != 0 {
+        " (proxy)"
+    } else {
+        ""
+    };
+    format!("Network: Connected{}", kind)
+}
+
+fn filetime_to_u64(time: &FILETIME) -> u64 {
+    ((time.dwHighDateTime as u64) << 32) | time.dwLowDateTime as u64
+}
+
+// CPU load since the previous call; None on the first call, when there is nothing to compare with
+fn get_cpu_usage() -> Option<u32> {
+    let mut idle: FILETIME = unsafe { std::mem::zeroed() };
+    let mut kernel: FILETIME = unsafe { std::mem::zeroed() };
+    let mut user: FILETIME = unsafe { std::mem::zeroed() };
+    if unsafe { GetSystemTimes(&mut idle, &mut kernel, &mut user) } == 0 {
+        return None;
+    }
+
+    // Kernel time includes idle time
+    let idle = filetime_to_u64(&idle);
+    let total = filetime_to_u64(&kernel) + filetime_to_u64(&user);
+
+    let mut last_sample = LAST_CPU_SAMPLE.lock().unwrap_or_else(|e| e.into_inner());
+    let previous = last_sample.replace((idle, total));
+    let (previous_idle, previous_total) = previous?;
+
+    let total_delta = total.saturating_sub(previous_total);
+    if total_delta == 0 {
+        return None;
+    }
+    let busy_delta = total_delta.saturating_sub(idle.saturating_sub(previous_idle));
+    Some((busy_delta * 100 / total_delta) as u32)
+}
+
+fn get_memory_usage() -> Option<String> {
+    let mut status: MEMORYSTATUSEX = unsafe { std::mem::zeroed() };
+    status.dwLength = std::mem::size_of::<MEMORYSTATUSEX>() as u32;
+    let memory = if unsafe { GlobalMemoryStatusEx(&mut status) } != 0 {
+        Some(format!("RAM: {}%", status.dwMemoryLoad))
+    } else {
+        None
+    };
+
+    match (get_cpu_usage(), memory) {
+        (Some(cpu), Some(memory)) => Some(format!("CPU: {}%, {}", cpu, memory)),
+        (Some(cpu), None) => Some(format!("CPU: {}%", cpu)),
+        (None, memory) => memory,
+    }
+}
+
+// One "NYC: 09:14" line per configured zone, marked when the zone is on another day than us
+fn get_secondary_timezones(time: &dyn TimeSource) -> Option<String> {
+    let zones = settings::get()?.timezones.load()?;
+    secondary_timezones_text(&zones, &time.now().time, &line_separator())
+}
+
+// One line per "<offset hours>\t<label>" entry of `zones`, joined with `separator` like the sections
+fn secondary_timezones_text(zones: &str, now: &DateTime<FixedOffset>, separator: &str) -> Option<String> {
+    let local_date = now.date_naive();
+
+    let time_format = time_format();
+    let lines: Vec<String> = zones
+        .lines()
+        .filter_map(|zone| {
+            let (offset_hours, label) = zone.split_once('\t')?;
+            let offset = FixedOffset::east_opt(offset_hours.parse::<i32>().ok()? * 3600)?;
+            let zone_time = now.with_timezone(&offset);
+            let day_marker = match (zone_time.date_naive() - local_date).num_days() {
+                0 => String::new(),
+                days => format!(" ({:+}d)", days),
+            };
+            Some(format!("{}: {}{}", label, zone_time.format(time_format), day_marker))
+        })
+        .collect();
+
+    if lines.is_empty() {
+        None
+    } else {
+        Some(lines.join(separator))
+    }
+}
+
+fn day_of_year_text(date: NaiveDate) -> String {
+    let days_in_year = if date.leap_year() { 366 } else { 365 };
+    let day = date.ordinal();
+    format!("Day {} of {} ({} left)", day, days_in_year, days_in_year - day)
+}
+
+// Mean length of a lunar cycle in days, and a new moon to count cycles from (2000-01-06 18:14 UTC)
+const SYNODIC_MONTH_DAYS: f64 = 29.530588853;
+const REFERENCE_NEW_MOON_JULIAN_DAY: f64 = 2451550.26;
+// Julian day number of 1970-01-01 00:00 UTC
+const UNIX_EPOCH_JULIAN_DAY: f64 = 2440587.5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MoonPhase {
+    NewMoon,
+    WaxingCrescent,
+    FirstQuarter,
+    WaxingGibbous,
+    FullMoon,
+    WaningGibbous,
+    LastQuarter,
+    WaningCrescent,
+}
+
+impl MoonPhase {
+    fn text(self) -> &'static str {
+        match self {
+            MoonPhase::NewMoon => "\u{1F311} New Moon",
+            MoonPhase::WaxingCrescent => "\u{1F312} Waxing Crescent",
+            MoonPhase::FirstQuarter => "\u{1F313} First Quarter",
+            MoonPhase::WaxingGibbous => "\u{1F314} Waxing Gibbous",
+            MoonPhase::FullMoon => "\u{1F315} Full Moon",
+            MoonPhase::WaningGibbous => "\u{1F316} Waning Gibbous",
+            MoonPhase::LastQuarter => "\u{1F317} Last Quarter",
+            MoonPhase::WaningCrescent => "\u{1F318} Waning Crescent",
+        }
+    }
+}
+
+// Days since the last mean new moon, at noon UTC on `date`
+fn moon_age(date: NaiveDate) -> f64 {
+    let noon = date.and_hms_opt(12, 0, 0).unwrap_or_default().and_utc();
+    let julian_day = noon.timestamp() as f64 / 86400.0 + UNIX_EPOCH_JULIAN_DAY;
+    (julian_day - REFERENCE_NEW_MOON_JULIAN_DAY).rem_euclid(SYNODIC_MONTH_DAYS)
+}
+
+// Uses the mean synodic month, which is within about a day of the true phase
+fn moon_phase(date: NaiveDate) -> MoonPhase {
+    const PHASES: [MoonPhase; 8] = [
+        MoonPhase::NewMoon,
+        MoonPhase::WaxingCrescent,
+        MoonPhase::FirstQuarter,
+        MoonPhase::WaxingGibbous,
+        MoonPhase::FullMoon,
+        MoonPhase::WaningGibbous,
+        MoonPhase::LastQuarter,
+        MoonPhase::WaningCrescent,
+    ];
+    // Each phase is centered on its eighth of the cycle
+    let index = (moon_age(date) / SYNODIC_MONTH_DAYS * 8.0 + 0.5) as usize % PHASES.len();
+    PHASES[index]
+}
+
+// Julian day number of 2000-01-01 12:00 UTC, the J2000 epoch the solar formulas count from
+const J2000_JULIAN_DAY: f64 = 2451545.0;
+// Sun's apparent radius plus atmospheric refraction at the horizon
+const SUNRISE_ALTITUDE_DEGREES: f64 = -0.833;
+
+// Sunrise and sunset in UTC using the sunrise equation; None during polar day or night
+fn sun_times(date: NaiveDate, latitude: f64, longitude: f64) -> Option<(NaiveTime, NaiveTime)> {
+    let j2000 = NaiveDate::from_ymd_opt(2000, 1, 1)?;
+    let day = (date - j2000).num_days() as f64 + 0.0008;
+
+    let mean_solar_time = day - longitude / 360.0;
+    let mean_anomaly = (357.5291 + 0.98560028 * mean_solar_time).rem_euclid(360.0).to_radians();
+    let center = 1.9148 * mean_anomaly.sin() + 0.02 * (2.0 * mean_anomaly).sin() + 0.0003 * (3.0 * mean_anomaly).sin();
+    let ecliptic_longitude = (mean_anomaly.to_degrees() + center + 180.0 + 102.9372).rem_euclid(360.0).to_radians();
+    let transit = J2000_JULIAN_DAY + mean_solar_time + 0.0053 * mean_anomaly.sin()
+        - 0.0069 * (2.0 * ecliptic_longitude).sin();
+
+    let declination = (ecliptic_longitude.sin() * 23.4397_f64.to_radians().sin()).asin();
+    let latitude = latitude.to_radians();
+    let cos_hour_angle = (SUNRISE_ALTITUDE_DEGREES.to_radians().sin() - latitude.sin() * declination.sin())
+        / (latitude.cos() * declination.cos());
+    if !(-1.0..=1.0).contains(&cos_hour_angle) {
+        return None;
+    }
+
+    let hour_angle_days = cos_hour_angle.acos().to_degrees() / 360.0;
+    Some((
+        julian_day_to_utc_time(transit - hour_angle_days)?,
+        julian_day_to_utc_time(transit + hour_angle_days)?,
+    ))
+}
+
+fn julian_day_to_utc_time(julian_day: f64) -> Option<NaiveTime> {
+    let seconds = ((julian_day - UNIX_EPOCH_JULIAN_DAY) * 86400.0).round() as i64;
+    chrono::DateTime::from_timestamp(seconds, 0).map(|time| time.time())
+}
+
+// "☀ 06:12 / 🌙 20:44" in local time, with dashes while the sun doesn't rise or set
+fn get_sun_times(time: &dyn TimeSource) -> Option<String> {
+    let location = settings::get()?.location.load()?;
+    let (latitude, longitude) = location.split_once(',')?;
+    let (latitude, longitude) = (latitude.parse::<f64>().ok()?, longitude.parse::<f64>().ok()?);
+
+    let now = time.now().time;
+    let offset = now.offset().fix();
+    let time_format = time_format();
+    let text = match sun_times(now.date_naive(), latitude, longitude) {
+        Some((sunrise, sunset)) => format!(
+            "\u{2600} {} / \u{1F319} {}",
+            (sunrise + offset).format(time_format),
+            (sunset + offset).format(time_format)
+        ),
+        None => "\u{2600} \u{2014} / \u{1F319} \u{2014}".to_string(),
+    };
+    Some(text)
+}
+
+fn section_text(name: &str, time: &dyn TimeSource) -> Option<String> {
+    match name.trim() {
+        "uptime" => Some(format!("Opptid: {}", get_uptime(time))),
+        "week" => Some(get_norwegian_week(time)),
+        "datetime" => Some(get_datetime(time)),
+        "battery" => get_battery(),
+        "memory" => get_memory_usage(),
+        "network" => Some(get_network_status()),
+        "countdown" => get_countdown(time),
+        "timezones" => get_secondary_timezones(time),
+        "day_of_year" => Some(day_of_year_text(time.now().time.date_naive())),
+        "sun" => get_sun_times(time),
+        "moon" => Some(moon_phase(time.now().time.date_naive()).text().to_string()),
+        _ => None,
+    }
+}
+
+// Lines appended below the native clock tooltip text
+pub fn generate_tooltip_text() -> String {
+    generate_tooltip_text_at(&SystemTime)
+}
+
+fn generate_tooltip_text_at(time: &dyn TimeSource) -> String {
+    let settings = settings::get();
+    if let Some(custom_text) = settings.and_then(|settings| settings.custom_text.load()) {
+        return custom_text;
+    }
+
+    let order = settings
+        .and_then(|settings| settings.section_order.load())
+        .unwrap_or_else(|| DEFAULT_SECTION_ORDER.to_string());
+    let decorations = settings.and_then(|settings| settings.decorations.load()).unwrap_or_default();
+    order
+        .split(',')
+        .filter_map(|name| section_text(name, time).map(|text| decorate(&decorations, name.trim(), text)))
+        .collect::<Vec<_>>()
+        .join(&line_separator())
+}
+
+pub fn line_separator() -> String {
+    settings::get()
+        .and_then(|settings| settings.line_separator.load())
+        .unwrap_or_else(|| DEFAULT_LINE_SEPARATOR.to_string())
+}
+
+// Prefixes `text` with the marker configured for `section`, if any
+fn decorate(decorations: &str, section: &str, text: String) -> String {
+    decorations
+        .lines()
+        .filter_map(|line| line.split_once('\t'))
+        .find(|(name, _)| *name == section)
+        .map_or(text.clone(), |(_, marker)| format!("{} {}", marker, text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time::FakeTime;
+    use chrono::{TimeZone, Utc};
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn format_duration_leaves_out_leading_zero_units() {
+        assert_eq!(format_duration(0), "0m");
+        assert_eq!(format_duration(59), "0m");
+        assert_eq!(format_duration(60), "1m");
+        assert_eq!(format_duration(3599), "59m");
+        assert_eq!(format_duration(3600), "1h 0m");
+        assert_eq!(format_duration(86399), "23h 59m");
+        assert_eq!(format_duration(86400), "1d 0h 0m");
+        assert_eq!(format_duration(2 * 86400 + 4 * 3600 + 13 * 60 + 30), "2d 4h 13m");
+    }
+
+    #[test]
+    fn format_uptime_right_after_boot_and_past_32_bit_ticks() {
+        assert_eq!(format_uptime(0, UPTIME_PRECISION_MINUTES), "0m");
+        assert_eq!(format_uptime(59_000, UPTIME_PRECISION_MINUTES), "0m");
+        assert_eq!(format_uptime(3_600_000, UPTIME_PRECISION_MINUTES), "1h 0m");
+        // Where GetTickCount would have wrapped back to 0
+        assert_eq!(format_uptime(u32::MAX as u64 + 1, UPTIME_PRECISION_MINUTES), "49d 17h 2m");
+        assert_eq!(format_uptime(u32::MAX as u64 + 1 + 60_000, UPTIME_PRECISION_MINUTES), "49d 17h 3m");
+        assert_eq!(format_uptime(u64::MAX, UPTIME_PRECISION_MINUTES), "213503982334d 14h 25m");
+    }
+
+    #[test]
+    fn format_uptime_with_seconds() {
+        assert_eq!(format_uptime(0, UPTIME_PRECISION_SECONDS), "0s");
+        assert_eq!(format_uptime(59_999, UPTIME_PRECISION_SECONDS), "59s");
+        assert_eq!(format_uptime(60_000, UPTIME_PRECISION_SECONDS), "1m 0s");
+        assert_eq!(format_uptime(3_725_000, UPTIME_PRECISION_SECONDS), "1h 2m 5s");
+        assert_eq!(format_uptime((2 * 86400 + 3 * 60 + 9) * 1000, UPTIME_PRECISION_SECONDS), "2d 0h 3m 9s");
+    }
+
+    #[test]
+    fn day_of_year_counts_leap_days() {
+        assert_eq!(day_of_year_text(date(2025, 1, 1)), "Day 1 of 365 (364 left)");
+        assert_eq!(day_of_year_text(date(2025, 12, 31)), "Day 365 of 365 (0 left)");
+        assert_eq!(day_of_year_text(date(2024, 12, 31)), "Day 366 of 366 (0 left)");
+        assert_eq!(day_of_year_text(date(2024, 3, 1)), "Day 61 of 366 (305 left)");
+    }
+
+    // Days between `date` and the nearest mean new moon
+    fn days_from_new_moon(date: NaiveDate) -> f64 {
+        let age = moon_age(date);
+        age.min(SYNODIC_MONTH_DAYS - age)
+    }
+
+    #[test]
+    fn moon_phase_on_known_new_moons() {
+        for new_moon in [date(2023, 11, 13), date(2024, 1, 11), date(2024, 4, 8), date(2025, 9, 21)] {
+            assert_eq!(moon_phase(new_moon), MoonPhase::NewMoon, "{}", new_moon);
+            assert!(days_from_new_moon(new_moon) <= 1.0, "{}", new_moon);
+        }
+    }
+
+    #[test]
+    fn moon_phase_on_known_full_moons() {
+        for full_moon in [date(2023, 8, 31), date(2024, 1, 25), date(2024, 3, 25), date(2025, 9, 7)] {
+            assert_eq!(moon_phase(full_moon), MoonPhase::FullMoon, "{}", full_moon);
+            let half_cycle = SYNODIC_MONTH_DAYS / 2.0;
+            assert!((moon_age(full_moon) - half_cycle).abs() <= 1.0, "{}", full_moon);
+        }
+    }
+
+    #[test]
+    fn moon_phase_on_known_quarters() {
+        // 2024-01-18 first quarter, 2024-02-02 last quarter
+        assert_eq!(moon_phase(date(2024, 1, 18)), MoonPhase::FirstQuarter);
+        assert_eq!(moon_phase(date(2024, 2, 2)), MoonPhase::LastQuarter);
+    }
+
+    fn assert_near(actual: NaiveTime, expected: NaiveTime) {
+        let minutes = (actual - expected).num_minutes().abs();
+        assert!(minutes <= 3, "{} is {} minutes from {}", actual, minutes, expected);
+    }
+
+    fn time(hour: u32, minute: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn sun_times_in_oslo() {
+        // Published times in UTC, Oslo (59.91N, 10.75E)
+        let (sunrise, sunset) = sun_times(date(2024, 6, 21), 59.91, 10.75).unwrap();
+        assert_near(sunrise, time(1, 54));
+        assert_near(sunset, time(20, 44));
+
+        let (sunrise, sunset) = sun_times(date(2024, 12, 21), 59.91, 10.75).unwrap();
+        assert_near(sunrise, time(8, 18));
+        assert_near(sunset, time(14, 12));
+    }
+
+    #[test]
+    fn sun_times_west_of_greenwich() {
+        // New York (40.71N, 74.01W) at the March equinox
+        let (sunrise, sunset) = sun_times(date(2024, 3, 20), 40.71, -74.01).unwrap();
+        assert_near(sunrise, time(11, 2));
+        assert_near(sunset, time(23, 10));
+    }
+
+    #[test]
+    fn sun_times_polar_day_and_night() {
+        // Tromsø (69.65N, 18.96E): midnight sun in June, polar night in December
+        assert_eq!(sun_times(date(2024, 6, 21), 69.65, 18.96), None);
+        assert_eq!(sun_times(date(2024, 12, 21), 69.65, 18.96), None);
+    }
+
+    #[test]
+    fn iso_week_of_january_first() {
+        // Monday: week 1 starts on January 1
+        assert_eq!(week_number(date(2024, 1, 1), WEEK_SCHEME_ISO), (1, 2024));
+        // Thursday: still belongs to week 1
+        assert_eq!(week_number(date(2026, 1, 1), WEEK_SCHEME_ISO), (1, 2026));
+        // Friday: last week of the previous year, which had 53 weeks
+        assert_eq!(week_number(date(2021, 1, 1), WEEK_SCHEME_ISO), (53, 2020));
+        // Saturday and Sunday: last week of the previous year
+        assert_eq!(week_number(date(2022, 1, 1), WEEK_SCHEME_ISO), (52, 2021));
+        assert_eq!(week_number(date(2023, 1, 1), WEEK_SCHEME_ISO), (52, 2022));
+        // Late December can already be week 1 of the next year
+        assert_eq!(week_number(date(2019, 12, 30), WEEK_SCHEME_ISO), (1, 2020));
+    }
+
+    #[test]
+    fn us_week_of_january_first() {
+        for year in [2021, 2022, 2023, 2024, 2026] {
+            assert_eq!(week_number(date(year, 1, 1), WEEK_SCHEME_US_SUNDAY_START), (1, year));
+        }
+    }
+
+    #[test]
+    fn us_weeks_start_on_sunday() {
+        // 2022-01-01 is a Saturday, so week 2 starts the next day
+        assert_eq!(week_number(date(2022, 1, 1), WEEK_SCHEME_US_SUNDAY_START), (1, 2022));
+        assert_eq!(week_number(date(2022, 1, 2), WEEK_SCHEME_US_SUNDAY_START), (2, 2022));
+        // 2023-01-01 is a Sunday, so week 1 is a full week
+        assert_eq!(week_number(date(2023, 1, 7), WEEK_SCHEME_US_SUNDAY_START), (1, 2023));
+        assert_eq!(week_number(date(2023, 1, 8), WEEK_SCHEME_US_SUNDAY_START), (2, 2023));
+        // December 31 stays in the current year, in week 53 when the year started late in the week
+        assert_eq!(week_number(date(2022, 12, 31), WEEK_SCHEME_US_SUNDAY_START), (53, 2022));
+        assert_eq!(week_number(date(2023, 12, 31), WEEK_SCHEME_US_SUNDAY_START), (53, 2023));
+    }
+
+    // 2024-12-31 23:30 UTC, which is already 2025 an hour east
+    fn new_year_in_oslo(offset_hours: Option<i32>) -> FakeTime {
+        FakeTime {
+            utc: Utc.with_ymd_and_hms(2024, 12, 31, 23, 30, 0).unwrap(),
+            offset_hours,
+            uptime_ms: 3_725_000,
+        }
+    }
+
+    // The date/time line follows the machine's locale, so it is compared with the section itself
+    #[test]
+    fn default_tooltip_text_at_a_fixed_time() {
+        let time = new_year_in_oslo(Some(1));
+        let datetime = section_text("datetime", &time).unwrap();
+        assert_eq!(generate_tooltip_text_at(&time), format!("Opptid: 1h 2m\nUke 1\n{}", datetime));
+    }
+
+    #[test]
+    fn default_tooltip_text_marks_the_utc_fallback() {
+        // 2024-12-31 is in ISO week 1 of 2025
+        let time = new_year_in_oslo(None);
+        let datetime = section_text("datetime", &time).unwrap();
+        assert_eq!(generate_tooltip_text_at(&time), format!("Opptid: 1h 2m\nUke 1 (UTC)\n{}", datetime));
+    }
+
+    #[test]
+    fn datetime_line_uses_the_local_date_and_marks_the_utc_fallback() {
+        let line = section_text("datetime", &new_year_in_oslo(Some(1))).unwrap();
+        assert!(line.contains("2025") && line.contains("00:30"), "unexpected line {:?}", line);
+        let line = section_text("datetime", &new_year_in_oslo(None)).unwrap();
+        assert!(line.contains("2024") && line.ends_with("23:30 (UTC)"), "unexpected line {:?}", line);
+    }
+
+    #[test]
+    fn secondary_timezones_on_one_row() {
+        let now = new_year_in_oslo(Some(1)).now().time;
+        assert_eq!(
+            secondary_timezones_text("-5\tNYC\n9\tTokyo", &now, " | ").unwrap(),
+            "NYC: 18:30 (-1d) | Tokyo: 08:30"
+        );
+    }
+
+    #[test]
+    fn secondary_timezones_mark_other_days_around_new_year() {
+        // Local time is 2025-01-01 00:30, so zones west of UTC+1 are still in 2024
+        let now = new_year_in_oslo(Some(1)).now().time;
+        assert_eq!(
+            secondary_timezones_text("-5\tNYC\n0\tUTC", &now, "\n").unwrap(),
+            "NYC: 18:30 (-1d)\nUTC: 23:30 (-1d)"
+        );
+        // Local time is 2024-12-31 18:30, so Oslo is already in 2025 but Los Angeles is not
+        let now = new_year_in_oslo(Some(-5)).now().time;
+        assert_eq!(
+            secondary_timezones_text("1\tOslo\n-8\tLA", &now, "\n").unwrap(),
+            "Oslo: 00:30 (+1d)\nLA: 15:30"
+        );
+    }
+
+    #[test]
+    fn date_sections_use_the_local_date() {
+        let time = new_year_in_oslo(Some(1));
+        assert_eq!(section_text("day_of_year", &time).unwrap(), "Day 1 of 365 (364 left)");
+        let time = new_year_in_oslo(Some(-5));
+        assert_eq!(section_text("day_of_year", &time).unwrap(), "Day 366 of 366 (0 left)");
+    }
+}
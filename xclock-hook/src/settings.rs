@@ -0,0 +1,161 @@
+// Settings shared by every process the hook DLL is loaded into.
+//
+// The main application writes them through the exported setters while the DLL copies injected
+// into other processes read them, so they live in a named file mapping rather than in statics.
+// Every field is an atomic, which keeps a freshly created (zeroed) mapping valid and lets readers
+// inside Explorer never block on the writer.
+
+use std::ptr;
+use std::sync::atomic::{fence, AtomicPtr, AtomicU16, AtomicU32, Ordering};
+use std::sync::Mutex;
+use winapi::ctypes::c_void;
+use winapi::shared::winerror::ERROR_ALREADY_EXISTS;
+use winapi::um::errhandlingapi::GetLastError;
+use winapi::um::handleapi::{CloseHandle, INVALID_HANDLE_VALUE};
+use winapi::um::memoryapi::{CreateFileMappingW, MapViewOfFile, UnmapViewOfFile, FILE_MAP_ALL_ACCESS};
+use winapi::um::winnt::{HANDLE, PAGE_READWRITE};
+
+use xclock_util::wide::to_wide;
+
+use crate::{debug_log, debug_logf};
+
+const MAPPING_NAME_PREFIX: &str = "Local\\XClockHookSettings";
+const MAX_CUSTOM_TEXT: usize = 1024;
+const MAX_SECTION_ORDER: usize = 256;
+const MAX_LABEL_FORMAT: usize = 128;
+const MAX_TIMEZONES: usize = 512;
+const MAX_DECORATIONS: usize = 256;
+const MAX_LOCATION: usize = 64;
+const MAX_COUNTDOWN: usize = 128;
+const MAX_LINE_SEPARATOR: usize = 16;
+// How many times a reader retries while the writer is mid-update before giving up
+const READ_ATTEMPTS: usize = 8;
+
+static MAPPING: AtomicPtr<c_void> = AtomicPtr::new(ptr::null_mut());
+static SETTINGS: AtomicPtr<SharedSettings> = AtomicPtr::new(ptr::null_mut());
+// Serializes writers within the controlling process
+static WRITE_LOCK: Mutex<()> = Mutex::new(());
+
+#[repr(C)]
+pub struct SharedSettings {
+    pub custom_text: SharedText<MAX_CUSTOM_TEXT>,
+    // Comma-separated section names, e.g. "week,uptime"
+    pub section_order: SharedText<MAX_SECTION_ORDER>,
+    // Week line with {week} and {year} placeholders
+    pub week_label_format: SharedText<MAX_LABEL_FORMAT>,
+    // strftime-style pattern for the date/time line
+    pub datetime_format: SharedText<MAX_LABEL_FORMAT>,
+    // One "<offset hours>\t<label>" line per secondary timezone
+    pub timezones: SharedText<MAX_TIMEZONES>,
+    // Minimum time between two tooltip rewrites in milliseconds plus one, 0 while unset
+    pub cooldown_ms: AtomicU32,
+    // Non-zero while the host has paused tooltip rewriting
+    pub suppressed: AtomicU32,
+    // 0 = ISO-8601 weeks, 1 = US weeks starting on Sunday
+    pub week_scheme: AtomicU32,
+    // One "<section>\t<marker>" line per decorated section; empty when decorations are off
+    pub decorations: SharedText<MAX_DECORATIONS>,
+    // "<latitude>,<longitude>" in degrees for the sunrise/sunset line
+    pub location: SharedText<MAX_LOCATION>,
+    // "<target unix timestamp>\t<label>" for the countdown line
+    pub countdown: SharedText<MAX_COUNTDOWN>,
+    // Non-zero for 12-hour times with AM/PM
+    pub time_12h: AtomicU32,
+    // 0 = uptime in minutes, 1 = with seconds
+    pub uptime_precision: AtomicU32,
+    // Maximum classic tooltip width in pixels plus one (1 = no cap), 0 while unset
+    pub max_tip_width: AtomicU32,
+    // Virtual-key code that must be held for the tooltip to be extended, 0 to always extend it
+    pub activation_key: AtomicU32,
+    // Text between the section lines, e.g. " | "; empty means a newline
+    pub line_separator: SharedText<MAX_LINE_SEPARATOR>,
+}
+
+// UTF-16 text guarded by a sequence counter: odd while a write is in progress
+#[repr(C)]
+pub struct SharedText<const N: usize> {
+    seq: AtomicU32,
+    len: AtomicU32,
+    buf: [AtomicU16; N],
+}
+
+impl<const N: usize> SharedText<N> {
+    pub fn store(&self, text: &str) {
+        let _guard = WRITE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let utf16: Vec<u16> = text.encode_utf16().take(N).collect();
+
+        self.seq.fetch_add(1, Ordering::AcqRel);
+        for (slot, unit) in self.buf.iter().zip(&utf16) {
+            slot.store(*unit, Ordering::Relaxed);
+        }
+        self.len.store(utf16.len() as u32, Ordering::Relaxed);
+        self.seq.fetch_add(1, Ordering::Release);
+    }
+
+    // None if the text is empty or couldn't be read consistently
+    pub fn load(&self) -> Option<String> {
+        for _ in 0..READ_ATTEMPTS {
+            let before = self.seq.load(Ordering::Acquire);
+            if before % 2 == 1 {
+                std::thread::yield_now();
+                continue;
+            }
+
+            let len = (self.len.load(Ordering::Relaxed) as usize).min(N);
+            let utf16: Vec<u16> = self.buf[..len].iter().map(|unit| unit.load(Ordering::Relaxed)).collect();
+
+            fence(Ordering::Acquire);
+            if self.seq.load(Ordering::Relaxed) == before {
+                return if utf16.is_empty() { None } else { Some(String::from_utf16_lossy(&utf16)) };
+            }
+        }
+        None
+    }
+}
+
+pub unsafe fn open() {
+    // The layout size is part of the name so a DLL from another build never maps a mismatched struct
+    let name = to_wide(&format!("{}.{}", MAPPING_NAME_PREFIX, std::mem::size_of::<SharedSettings>()));
+    let mapping = CreateFileMappingW(
+        INVALID_HANDLE_VALUE,
+        ptr::null_mut(),
+        PAGE_READWRITE,
+        0,
+        std::mem::size_of::<SharedSettings>() as u32,
+        name.as_ptr(),
+    );
+    if mapping.is_null() {
+        debug_logf("Failed to create settings mapping, error code: {0}", &[&GetLastError()]);
+        return;
+    }
+    let existed = GetLastError() == ERROR_ALREADY_EXISTS;
+
+    let view = MapViewOfFile(mapping, FILE_MAP_ALL_ACCESS, 0, 0, 0);
+    if view.is_null() {
+        debug_logf("Failed to map settings view, error code: {0}", &[&GetLastError()]);
+        CloseHandle(mapping);
+        return;
+    }
+
+    MAPPING.store(mapping, Ordering::SeqCst);
+    SETTINGS.store(view as *mut SharedSettings, Ordering::SeqCst);
+    debug_logf("Settings mapping opened (existing: {0})", &[&existed]);
+}
+
+pub unsafe fn close() {
+    let view = SETTINGS.swap(ptr::null_mut(), Ordering::SeqCst);
+    if !view.is_null() {
+        UnmapViewOfFile(view as *const _);
+    }
+    let mapping: HANDLE = MAPPING.swap(ptr::null_mut(), Ordering::SeqCst);
+    if !mapping.is_null() {
+        CloseHandle(mapping);
+    }
+    debug_log("Settings mapping closed");
+}
+
+pub fn get() -> Option<&'static SharedSettings> {
+    let view = SETTINGS.load(Ordering::SeqCst);
+    // The view stays mapped until DLL_PROCESS_DETACH
+    unsafe { view.as_ref() }
+}
@@ -0,0 +1,111 @@
+// Where the tooltip sections read the time from, so tests can pin it.
+
+use chrono::{DateTime, FixedOffset, Local, NaiveDateTime, Offset, TimeDelta, TimeZone, Utc};
+use winapi::um::sysinfoapi::GetTickCount64;
+
+use crate::debug_log;
+
+const UTC_FALLBACK_SUFFIX: &str = " (UTC)";
+
+// Current time for the tooltip lines. When the system timezone can't be read this is UTC instead,
+// and `utc_fallback` is set so the week and date/time lines can say so
+pub struct ClockTime {
+    pub time: DateTime<FixedOffset>,
+    pub utc_fallback: bool,
+}
+
+impl ClockTime {
+    pub fn utc_suffix(&self) -> &'static str {
+        if self.utc_fallback { UTC_FALLBACK_SUFFIX } else { "" }
+    }
+}
+
+pub trait TimeSource {
+    fn now(&self) -> ClockTime;
+    // Milliseconds since boot
+    fn uptime_ms(&self) -> u64;
+}
+
+// The system clock and tick count, used for the real tooltip
+pub struct SystemTime;
+
+impl TimeSource for SystemTime {
+    fn now(&self) -> ClockTime {
+        clock_time_at(Utc::now(), local_offset)
+    }
+
+    fn uptime_ms(&self) -> u64 {
+        // GetTickCount64 doesn't wrap after 49.7 days like GetTickCount does
+        unsafe { GetTickCount64() }
+    }
+}
+
+// `local_offset` is passed in so tests can simulate a broken timezone
+fn clock_time_at(utc: DateTime<Utc>, local_offset: impl Fn(&NaiveDateTime) -> Option<FixedOffset>) -> ClockTime {
+    match local_offset(&utc.naive_utc()) {
+        Some(offset) => ClockTime { time: utc.with_timezone(&offset), utc_fallback: false },
+        None => ClockTime { time: utc.fixed_offset(), utc_fallback: true },
+    }
+}
+
+// Local::now() unwraps the timezone lookup and would take Explorer down with it, so check first
+// with the local-to-UTC lookup, which reports the same failure as None. The second probe 12 hours
+// on keeps a DST gap from looking like a broken timezone
+fn local_offset(utc: &NaiveDateTime) -> Option<FixedOffset> {
+    let usable = [*utc, *utc + TimeDelta::hours(12)]
+        .iter()
+        .any(|probe| Local.offset_from_local_datetime(probe).earliest().is_some());
+    if !usable {
+        unsafe { debug_log("Local timezone unavailable, showing UTC") };
+        return None;
+    }
+    Some(Local.offset_from_utc_datetime(utc).fix())
+}
+
+// A fixed instant and uptime for tests; `offset_hours: None` behaves like a broken timezone
+#[cfg(test)]
+pub struct FakeTime {
+    pub utc: DateTime<Utc>,
+    pub offset_hours: Option<i32>,
+    pub uptime_ms: u64,
+}
+
+#[cfg(test)]
+impl TimeSource for FakeTime {
+    fn now(&self) -> ClockTime {
+        clock_time_at(self.utc, |_| self.offset_hours.and_then(|hours| FixedOffset::east_opt(hours * 3600)))
+    }
+
+    fn uptime_ms(&self) -> u64 {
+        self.uptime_ms
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn clock_time_uses_the_local_offset() {
+        let utc = Utc.with_ymd_and_hms(2024, 12, 31, 23, 30, 0).unwrap();
+        let now = clock_time_at(utc, |_| FixedOffset::east_opt(3600));
+        assert!(!now.utc_fallback);
+        assert_eq!(now.time.date_naive(), date(2025, 1, 1));
+        assert_eq!(now.utc_suffix(), "");
+    }
+
+    #[test]
+    fn clock_time_falls_back_to_utc_without_a_timezone() {
+        let utc = Utc.with_ymd_and_hms(2024, 12, 31, 23, 30, 0).unwrap();
+        let now = clock_time_at(utc, |_| None);
+        assert!(now.utc_fallback);
+        assert_eq!(now.time, utc);
+        assert_eq!(now.time.date_naive(), date(2024, 12, 31));
+        assert_eq!(now.utc_suffix(), " (UTC)");
+    }
+}
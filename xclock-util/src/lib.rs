@@ -0,0 +1,3 @@
+//! Helpers shared by the xclock library and the hook DLL.
+
+pub mod wide;
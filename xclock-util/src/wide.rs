@@ -0,0 +1,54 @@
+//! Conversions between Rust strings and the UTF-16 strings used by the Win32 API.
+
+/// Encodes `s` as UTF-16 with a terminating NUL, ready to pass as an `LPCWSTR`.
+pub fn to_wide(s: &str) -> Vec<u16> {
+    s.encode_utf16().chain(std::iter::once(0)).collect()
+}
+
+/// Decodes a UTF-16 buffer up to its first NUL, or all of it if there is none. Invalid
+/// surrogates are replaced with U+FFFD.
+pub fn from_wide(wide: &[u16]) -> String {
+    let len = wide.iter().position(|&unit| unit == 0).unwrap_or(wide.len());
+    String::from_utf16_lossy(&wide[..len])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_non_ascii() {
+        for text in ["", "Uke 42", "Søndag 12. oktober", "時計", "\u{1F4C5} Week", "Ünïcödé\ttab\nline"] {
+            assert_eq!(from_wide(&to_wide(text)), text);
+        }
+    }
+
+    #[test]
+    fn to_wide_appends_a_single_nul() {
+        assert_eq!(to_wide("ab"), vec![b'a' as u16, b'b' as u16, 0]);
+        assert_eq!(to_wide(""), vec![0]);
+    }
+
+    #[test]
+    fn from_wide_stops_at_the_first_nul() {
+        // Fixed-size buffers filled by GetWindowTextW and friends
+        let mut buffer = [0u16; 8];
+        buffer[..3].copy_from_slice(&[b'1' as u16, b'2' as u16, b':' as u16]);
+        assert_eq!(from_wide(&buffer), "12:");
+
+        // Anything after the terminator is stale data, not text
+        let stale = [b'a' as u16, 0, b'b' as u16, 0];
+        assert_eq!(from_wide(&stale), "a");
+    }
+
+    #[test]
+    fn from_wide_without_nul_uses_the_whole_slice() {
+        let wide: Vec<u16> = "12:34".encode_utf16().collect();
+        assert_eq!(from_wide(&wide), "12:34");
+    }
+
+    #[test]
+    fn from_wide_replaces_lone_surrogates() {
+        assert_eq!(from_wide(&[b'a' as u16, 0xD800, b'b' as u16]), "a\u{FFFD}b");
+    }
+}
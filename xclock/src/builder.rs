@@ -0,0 +1,91 @@
+// Chainable configuration for starting the clock hook in one call.
+
+use crate::{
+    set_datetime_format, set_detection_backend, set_hook_scope, set_section_order, set_week_label_format,
+    start_monitoring, stop_monitoring, DetectionBackend, HookScope, XClockError,
+};
+
+/// Collects tooltip settings and applies them before starting monitoring.
+///
+/// Settings left unset keep their current values, so the builder can be combined with the
+/// individual setters.
+#[derive(Debug, Default, Clone)]
+pub struct ClockHookBuilder {
+    section_order: Option<Vec<String>>,
+    week_label: Option<String>,
+    datetime_format: Option<String>,
+    detection_backend: Option<DetectionBackend>,
+    hook_scope: Option<HookScope>,
+}
+
+/// Handle to running monitoring returned by [`ClockHookBuilder::build_and_start`].
+#[derive(Debug)]
+pub struct ClockHook {
+    _private: (),
+}
+
+impl ClockHookBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// See [`set_section_order`].
+    pub fn section_order(mut self, sections: &[&str]) -> Self {
+        self.section_order = Some(sections.iter().map(|name| name.to_string()).collect());
+        self
+    }
+
+    /// See [`set_week_label_format`].
+    pub fn week_label(mut self, format: &str) -> Self {
+        self.week_label = Some(format.to_string());
+        self
+    }
+
+    /// See [`set_datetime_format`].
+    pub fn datetime_format(mut self, format: &str) -> Self {
+        self.datetime_format = Some(format.to_string());
+        self
+    }
+
+    /// See [`set_detection_backend`].
+    pub fn detection_backend(mut self, backend: DetectionBackend) -> Self {
+        self.detection_backend = Some(backend);
+        self
+    }
+
+    /// See [`set_hook_scope`].
+    pub fn hook_scope(mut self, scope: HookScope) -> Self {
+        self.hook_scope = Some(scope);
+        self
+    }
+
+    /// Applies the collected settings and calls [`start_monitoring`].
+    pub fn build_and_start(self) -> Result<ClockHook, XClockError> {
+        if let Some(sections) = &self.section_order {
+            let sections: Vec<&str> = sections.iter().map(String::as_str).collect();
+            set_section_order(&sections)?;
+        }
+        if let Some(format) = &self.week_label {
+            set_week_label_format(format);
+        }
+        if let Some(format) = &self.datetime_format {
+            set_datetime_format(format);
+        }
+        if let Some(backend) = self.detection_backend {
+            set_detection_backend(backend);
+        }
+        if let Some(scope) = self.hook_scope {
+            set_hook_scope(scope);
+        }
+
+        start_monitoring()?;
+        Ok(ClockHook { _private: () })
+    }
+}
+
+impl ClockHook {
+    /// Stops monitoring; equivalent to [`stop_monitoring`].
+    pub fn stop(self) {
+        stop_monitoring();
+    }
+}
@@ -0,0 +1,118 @@
+// Read-only lookup of the taskbar and clock windows, for diagnostics such as `xclock-cli detect`.
+//
+// The hook accepts tooltips that appear near any taskbar, so this reports every taskbar plus its
+// classic clock child (TrayClockWClass) where one exists. On Windows 11 the clock is drawn by
+// XAML inside the taskbar; there it is located through UI Automation and listed as "ClockButton".
+// Nothing here feeds back into tooltip matching: the clock rectangles are only reported.
+
+use std::ptr;
+use winapi::shared::windef::{HWND, RECT};
+use winapi::um::winuser::{FindWindowExW, GetClassNameW, GetWindowRect};
+
+use xclock_util::wide::{from_wide, to_wide};
+
+use crate::uia::CLOCK_AUTOMATION_ID;
+
+// Same classes the hook matches tooltips against
+const TASKBAR_CLASSES: [&str; 2] = ["Shell_TrayWnd", "Shell_SecondaryTrayWnd"];
+// Path from a taskbar to its classic clock window
+const CLOCK_PATH: [&str; 2] = ["TrayNotifyWnd", "TrayClockWClass"];
+
+/// Returns the class name and screen rectangle of every taskbar and clock window currently found,
+/// without loading or installing anything.
+///
+/// Where a taskbar has no classic clock window, the Windows 11 clock is looked up through UI
+/// Automation and reported under the name `"ClockButton"`; it is left out if UI Automation is
+/// unavailable.
+///
+/// This is for diagnostics only. The hook decides whether a tooltip belongs to the clock by its
+/// distance from a taskbar, not from these rectangles.
+pub fn detect_clock_windows() -> Vec<(String, RECT)> {
+    let mut found = Vec::new();
+    crate::enable_dpi_awareness();
+
+    unsafe {
+        for class in TASKBAR_CLASSES {
+            let class_name = to_wide(class);
+            let mut taskbar = FindWindowExW(ptr::null_mut(), ptr::null_mut(), class_name.as_ptr(), ptr::null());
+            while !taskbar.is_null() {
+                push_window(&mut found, taskbar);
+                if let Some(clock) = find_clock(taskbar) {
+                    push_window(&mut found, clock);
+                } else if let Some(rect) = crate::uia::find_clock_button(taskbar) {
+                    log::debug!(
+                        "Found {} in HWND {:?}: ({}, {}) - ({}, {})",
+                        CLOCK_AUTOMATION_ID, taskbar, rect.left, rect.top, rect.right, rect.bottom
+                    );
+                    found.push((CLOCK_AUTOMATION_ID.to_string(), rect));
+                }
+                taskbar = FindWindowExW(ptr::null_mut(), taskbar, class_name.as_ptr(), ptr::null());
+            }
+        }
+    }
+
+    found
+}
+
+/// Like [`detect_clock_windows`], but empty while monitoring isn't running.
+pub fn clock_regions() -> Vec<(String, RECT)> {
+    if !crate::is_running() {
+        return Vec::new();
+    }
+    detect_clock_windows()
+}
+
+unsafe fn find_clock(taskbar: HWND) -> Option<HWND> {
+    let mut parent = taskbar;
+    for class in CLOCK_PATH {
+        let class_name = to_wide(class);
+        parent = FindWindowExW(parent, ptr::null_mut(), class_name.as_ptr(), ptr::null());
+        if parent.is_null() {
+            return None;
+        }
+    }
+    Some(parent)
+}
+
+unsafe fn push_window(found: &mut Vec<(String, RECT)>, hwnd: HWND) {
+    let mut rect = RECT { left: 0, top: 0, right: 0, bottom: 0 };
+    if GetWindowRect(hwnd, &mut rect) == 0 {
+        return;
+    }
+
+    let mut class_name = [0u16; 256];
+    let len = GetClassNameW(hwnd, class_name.as_mut_ptr(), class_name.len() as i32);
+    let class_name = from_wide(&class_name[..len.max(0) as usize]);
+    log::debug!("Found {} HWND {:?}: ({}, {}) - ({}, {})", class_name, hwnd, rect.left, rect.top, rect.right, rect.bottom);
+    found.push((class_name, rect));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[ignore = "needs an interactive desktop with a taskbar"]
+    fn detects_the_taskbar_and_clock_windows() {
+        let windows = detect_clock_windows();
+        assert!(!windows.is_empty(), "no taskbar found");
+
+        for (class_name, rect) in &windows {
+            assert!(
+                TASKBAR_CLASSES.contains(&class_name.as_str())
+                    || class_name == CLOCK_PATH[1]
+                    || class_name == CLOCK_AUTOMATION_ID,
+                "unexpected class {}",
+                class_name
+            );
+            assert!(rect.right > rect.left && rect.bottom > rect.top, "empty rect for {}", class_name);
+        }
+        assert!(windows.iter().any(|(class_name, _)| class_name == "Shell_TrayWnd"));
+    }
+
+    #[test]
+    fn clock_regions_is_empty_while_stopped() {
+        assert!(!crate::is_running());
+        assert!(clock_regions().is_empty());
+    }
+}
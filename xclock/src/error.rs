@@ -0,0 +1,57 @@
+use std::fmt;
+
+/// Errors returned by the public xclock API.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum XClockError {
+    /// `start_monitoring` was called while monitoring is already active.
+    AlreadyRunning,
+    /// `xclock_hook.dll` could not be loaded from any of the searched locations.
+    DllLoadFailed,
+    /// The DLL named by `XCLOCK_HOOK_DLL` could not be loaded; carries the path and the
+    /// `GetLastError` code.
+    DllPathLoadFailed(String, u32),
+    /// The hook DLL was loaded but does not export the named function.
+    DllFunctionMissing(String),
+    /// `InstallHook` failed inside the DLL; carries the `SetWindowsHookExW` error code it recorded.
+    HookInstallFailed(u32),
+    /// `GetMessageW` failed in the message loop; carries the `GetLastError` code.
+    MessageLoopFailed(u32),
+    /// A tooltip section name that isn't one of `SECTION_NAMES`.
+    UnknownSection(String),
+    /// `RegisterClassW` failed; carries the `GetLastError` code.
+    ClassRegistrationFailed(u32),
+    /// `CreateWindowExW` failed; carries the `GetLastError` code.
+    WindowCreationFailed(u32),
+    /// The shell refused to add the notification-area icon.
+    TrayIconFailed,
+    /// A secondary timezone offset outside -23..=23 hours.
+    InvalidTimezoneOffset(i32),
+    /// A latitude outside -90..=90 or a longitude outside -180..=180 degrees.
+    InvalidLocation,
+    /// Called on a platform other than Windows.
+    Unsupported,
+}
+
+impl fmt::Display for XClockError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            XClockError::AlreadyRunning => write!(f, "Monitoring is already running"),
+            XClockError::DllLoadFailed => write!(f, "Failed to load xclock_hook.dll from any location"),
+            XClockError::DllPathLoadFailed(path, code) => {
+                write!(f, "Failed to load hook DLL from {} (error {})", path, code)
+            }
+            XClockError::DllFunctionMissing(name) => write!(f, "Function {} not found in DLL", name),
+            XClockError::HookInstallFailed(code) => write!(f, "Failed to install hook in DLL (error {})", code),
+            XClockError::MessageLoopFailed(code) => write!(f, "GetMessage failed (error {})", code),
+            XClockError::UnknownSection(name) => write!(f, "Unknown tooltip section: {}", name),
+            XClockError::ClassRegistrationFailed(code) => write!(f, "Failed to register window class (error {})", code),
+            XClockError::WindowCreationFailed(code) => write!(f, "Failed to create window (error {})", code),
+            XClockError::TrayIconFailed => write!(f, "Failed to add the notification-area icon"),
+            XClockError::InvalidTimezoneOffset(hours) => write!(f, "Invalid timezone offset: {} hours", hours),
+            XClockError::InvalidLocation => write!(f, "Latitude or longitude out of range"),
+            XClockError::Unsupported => write!(f, "xclock is only supported on Windows"),
+        }
+    }
+}
+
+impl std::error::Error for XClockError {}
@@ -0,0 +1,86 @@
+// Reacts to Explorer restarts.
+//
+// When explorer.exe is restarted the taskbar is recreated and the shell broadcasts the registered
+// "TaskbarCreated" message to all top-level windows. A hidden window created on the
+// `message_loop` thread listens for it, re-installs the hook against the new Explorer instance and
+// puts the notification-area icon back.
+
+use std::ptr;
+use std::sync::atomic::{AtomicPtr, AtomicU32, Ordering};
+use winapi::shared::minwindef::{LPARAM, LRESULT, UINT, WPARAM};
+use winapi::shared::windef::{HWND, HWND__};
+use winapi::shared::winerror::ERROR_CLASS_ALREADY_EXISTS;
+use winapi::um::errhandlingapi::GetLastError;
+use winapi::um::libloaderapi::GetModuleHandleW;
+use winapi::um::winuser::*;
+
+use xclock_util::wide::to_wide;
+
+use crate::XClockError;
+
+const WATCH_CLASS_NAME: &str = "XClockExplorerWatch";
+// Not exported by winapi 0.3
+const MSGFLT_ALLOW: u32 = 1;
+
+static WATCH_WINDOW: AtomicPtr<HWND__> = AtomicPtr::new(ptr::null_mut());
+static WM_TASKBAR_CREATED: AtomicU32 = AtomicU32::new(0);
+
+// Creates the watcher window on the calling thread; a no-op if it already exists
+pub(crate) unsafe fn start_watching() -> Result<(), XClockError> {
+    if !WATCH_WINDOW.load(Ordering::SeqCst).is_null() {
+        return Ok(());
+    }
+
+    let taskbar_created = to_wide("TaskbarCreated");
+    WM_TASKBAR_CREATED.store(RegisterWindowMessageW(taskbar_created.as_ptr()), Ordering::SeqCst);
+
+    register_watch_class()?;
+
+    // A hidden top-level window rather than HWND_MESSAGE: message-only windows miss broadcasts
+    let class_name = to_wide(WATCH_CLASS_NAME);
+    let hwnd = crate::window::create_hidden_window(&class_name, || register_watch_class())?;
+
+    // UIPI would otherwise drop the broadcast from a non-elevated Explorer when xclock runs elevated
+    ChangeWindowMessageFilterEx(hwnd, WM_TASKBAR_CREATED.load(Ordering::SeqCst), MSGFLT_ALLOW, ptr::null_mut());
+
+    WATCH_WINDOW.store(hwnd, Ordering::SeqCst);
+    Ok(())
+}
+
+unsafe fn register_watch_class() -> Result<(), XClockError> {
+    let class_name = to_wide(WATCH_CLASS_NAME);
+    let mut wnd_class: WNDCLASSW = std::mem::zeroed();
+    wnd_class.lpfnWndProc = Some(watch_window_proc);
+    wnd_class.hInstance = GetModuleHandleW(ptr::null());
+    wnd_class.lpszClassName = class_name.as_ptr();
+    if RegisterClassW(&wnd_class) == 0 {
+        let error = GetLastError();
+        if error != ERROR_CLASS_ALREADY_EXISTS {
+            return Err(XClockError::ClassRegistrationFailed(error));
+        }
+    }
+    Ok(())
+}
+
+pub(crate) unsafe fn stop_watching() {
+    let hwnd = WATCH_WINDOW.swap(ptr::null_mut(), Ordering::SeqCst);
+    if !hwnd.is_null() {
+        DestroyWindow(hwnd);
+    }
+}
+
+unsafe extern "system" fn watch_window_proc(hwnd: HWND, msg: UINT, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    let taskbar_created = WM_TASKBAR_CREATED.load(Ordering::SeqCst);
+    if taskbar_created != 0 && msg == taskbar_created {
+        log::info!("Explorer restarted - re-installing the hook");
+        if crate::is_running()
+            && let Err(e) = crate::restart_monitoring()
+        {
+            log::error!("Failed to restart monitoring after Explorer restart: {}", e);
+        }
+        crate::tray::restore_tray_icon();
+        return 0;
+    }
+
+    DefWindowProcW(hwnd, msg, wparam, lparam)
+}
@@ -0,0 +1,44 @@
+// C ABI for hosts that aren't written in Rust, built into xclock.dll.
+//
+// Every function must be called from the same thread, which also has to pump its messages:
+// either call `xclock_pump_messages` regularly (e.g. once per frame or from a timer) or run a
+// normal GetMessage/DispatchMessage loop. The hook DLL (xclock_hook.dll) still has to be next to
+// the executable, or its full path set in the XCLOCK_HOOK_DLL environment variable; this library
+// only loads and controls it.
+
+use winapi::shared::minwindef::{BOOL, FALSE, TRUE};
+
+fn to_bool(value: bool) -> BOOL {
+    if value { TRUE } else { FALSE }
+}
+
+/// Starts monitoring. Returns `FALSE` if it is already running or the hook could not be installed.
+#[unsafe(no_mangle)]
+pub extern "system" fn xclock_start() -> BOOL {
+    match crate::start_monitoring() {
+        Ok(()) => TRUE,
+        Err(e) => {
+            log::error!("xclock_start failed: {}", e);
+            FALSE
+        }
+    }
+}
+
+/// Stops monitoring and unloads the hook DLL. Always returns `TRUE`.
+#[unsafe(no_mangle)]
+pub extern "system" fn xclock_stop() -> BOOL {
+    crate::stop_monitoring();
+    TRUE
+}
+
+#[unsafe(no_mangle)]
+pub extern "system" fn xclock_is_running() -> BOOL {
+    to_bool(crate::is_running())
+}
+
+/// Dispatches the calling thread's pending messages without blocking. Returns `FALSE` once
+/// `WM_QUIT` has been received, after which the host should call `xclock_stop` and exit.
+#[unsafe(no_mangle)]
+pub extern "system" fn xclock_pump_messages() -> BOOL {
+    to_bool(crate::pump_messages())
+}
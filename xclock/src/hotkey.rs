@@ -0,0 +1,74 @@
+// Global hotkey that pauses and resumes tooltip extension.
+//
+// Registered without a window when monitoring starts, so WM_HOTKEY arrives in the queue of the
+// thread that called `start_monitoring`. `message_loop` and `pump_messages` both handle it there;
+// stopping monitoring releases the combination again.
+
+use std::ptr;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use winapi::um::errhandlingapi::GetLastError;
+use winapi::um::winuser::{RegisterHotKey, UnregisterHotKey, MOD_ALT, MOD_CONTROL, MSG, WM_HOTKEY};
+
+const HOTKEY_ID: i32 = 1;
+// Not exported by winapi 0.3; keeps a held key from toggling repeatedly
+const MOD_NOREPEAT: u32 = 0x4000;
+
+static MODIFIERS: AtomicU32 = AtomicU32::new((MOD_CONTROL | MOD_ALT) as u32);
+static VIRTUAL_KEY: AtomicU32 = AtomicU32::new(b'C' as u32);
+static REGISTERED: AtomicBool = AtomicBool::new(false);
+
+/// Sets the global hotkey that toggles between paused and active, e.g.
+/// `set_toggle_hotkey(MOD_CONTROL | MOD_SHIFT, 'T' as u32)`. The default is Ctrl+Alt+C and
+/// `vk == 0` disables the hotkey.
+///
+/// `modifiers` takes the `MOD_*` flags of `RegisterHotKey`. The hotkey is registered for the
+/// thread that calls `start_monitoring`, which must run `message_loop` or call `pump_messages`;
+/// a change takes effect the next time monitoring starts.
+/// [`is_toggle_hotkey_registered`] tells whether registration succeeded.
+pub fn set_toggle_hotkey(modifiers: u32, vk: u32) {
+    MODIFIERS.store(modifiers, Ordering::SeqCst);
+    VIRTUAL_KEY.store(vk, Ordering::SeqCst);
+}
+
+/// Whether the pause hotkey is currently registered. `false` while monitoring isn't running, when
+/// the hotkey is disabled, or when another application already owns the combination.
+pub fn is_toggle_hotkey_registered() -> bool {
+    REGISTERED.load(Ordering::SeqCst)
+}
+
+pub(crate) unsafe fn register() {
+    let vk = VIRTUAL_KEY.load(Ordering::SeqCst);
+    if vk == 0 || REGISTERED.load(Ordering::SeqCst) {
+        return;
+    }
+
+    let modifiers = MODIFIERS.load(Ordering::SeqCst) | MOD_NOREPEAT;
+    if RegisterHotKey(ptr::null_mut(), HOTKEY_ID, modifiers, vk) == 0 {
+        // Usually another application already owns the combination
+        log::warn!("Failed to register the pause hotkey (error {})", GetLastError());
+        return;
+    }
+    REGISTERED.store(true, Ordering::SeqCst);
+}
+
+pub(crate) unsafe fn unregister() {
+    if REGISTERED.swap(false, Ordering::SeqCst) {
+        UnregisterHotKey(ptr::null_mut(), HOTKEY_ID);
+    }
+}
+
+// Toggles pause if `msg` is our hotkey; returns whether it was consumed
+pub(crate) fn handle(msg: &MSG) -> bool {
+    if msg.message != WM_HOTKEY || !msg.hwnd.is_null() || msg.wParam != HOTKEY_ID as usize {
+        return false;
+    }
+
+    if crate::is_running() {
+        if crate::is_paused() {
+            crate::resume_monitoring();
+        } else {
+            crate::pause_monitoring();
+        }
+    }
+    true
+}
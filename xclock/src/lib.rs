@@ -1,136 +1,59 @@
 #![allow(unsafe_op_in_unsafe_fn)]
 
-use std::ptr;
-use std::sync::atomic::{AtomicBool, Ordering};
-use winapi::shared::minwindef::{BOOL, HMODULE};
-use winapi::um::libloaderapi::{FreeLibrary, GetProcAddress, LoadLibraryW};
-use winapi::um::winuser::*;
-
-// Global variables for thread communication
-static RUNNING: AtomicBool = AtomicBool::new(false);
-static mut HOOK_DLL: HMODULE = ptr::null_mut();
-
-// Function pointers for DLL functions
-type InstallHookFn = unsafe extern "system" fn() -> BOOL;
-type UninstallHookFn = unsafe extern "system" fn() -> BOOL;
-
-fn to_wide_string(s: &str) -> Vec<u16> {
-    s.encode_utf16().chain(std::iter::once(0)).collect()
-}
-
-unsafe fn load_hook_dll() -> Result<(), Box<dyn std::error::Error>> {
-    if !HOOK_DLL.is_null() {
-        return Ok(()); // Already loaded
-    }
-
-    // Try different possible paths for the DLL
-    let dll_paths = [
-        "xclock_hook.dll",                    // Current directory
-        "target\\release\\xclock_hook.dll",   // Build directory
-        "..\\target\\release\\xclock_hook.dll", // Parent build directory
-    ];
-
-    for dll_path in &dll_paths {
-        let dll_name = to_wide_string(dll_path);
-        HOOK_DLL = LoadLibraryW(dll_name.as_ptr());
-        
-        if !HOOK_DLL.is_null() {
-            println!("Loaded DLL from: {}", dll_path);
-            return Ok(());
-        }
-    }
-    
-    Err("Failed to load xclock_hook.dll from any location".into())
-}
-
-unsafe fn unload_hook_dll() {
-    if !HOOK_DLL.is_null() {
-        FreeLibrary(HOOK_DLL);
-        HOOK_DLL = ptr::null_mut();
-    }
-}
-
-unsafe fn call_dll_function<T>(func_name: &str) -> Result<T, Box<dyn std::error::Error>> 
-where
-    T: Copy,
-{
-    if HOOK_DLL.is_null() {
-        return Err("DLL not loaded".into());
-    }
-
-    let func_name_cstr = std::ffi::CString::new(func_name)?;
-    let func_ptr = GetProcAddress(HOOK_DLL, func_name_cstr.as_ptr());
-    
-    if func_ptr.is_null() {
-        return Err(format!("Function {} not found in DLL", func_name).into());
-    }
-
-    match func_name {
-        "InstallHook" => {
-            let install_hook: InstallHookFn = std::mem::transmute(func_ptr);
-            let result = install_hook();
-            Ok(*((&result) as *const BOOL as *const T))
-        }
-        "UninstallHook" => {
-            let uninstall_hook: UninstallHookFn = std::mem::transmute(func_ptr);
-            let result = uninstall_hook();
-            Ok(*((&result) as *const BOOL as *const T))
-        }
-        _ => Err(format!("Unknown function: {}", func_name).into()),
-    }
-}
-
-pub fn start_monitoring() -> Result<(), Box<dyn std::error::Error>> {
-    if RUNNING.load(Ordering::SeqCst) {
-        return Err("Monitoring is already running".into());
-    }
-
-    unsafe {
-        load_hook_dll()?;
-        
-        let result: BOOL = call_dll_function("InstallHook")?;
-        if result == 0 {
-            return Err("Failed to install hook in DLL".into());
-        }
-
-        RUNNING.store(true, Ordering::SeqCst);
-        println!("Global hook installed via DLL - monitoring tooltip creation across all processes");
-    }
-
-    Ok(())
-}
-
-pub fn stop_monitoring() {
-    RUNNING.store(false, Ordering::SeqCst);
-    
-    unsafe {
-        if !HOOK_DLL.is_null() {
-            let _result: Result<BOOL, _> = call_dll_function("UninstallHook");
-            unload_hook_dll();
-            println!("Hook removed and DLL unloaded");
-        }
-    }
-}
-
-pub fn is_running() -> bool {
-    RUNNING.load(Ordering::SeqCst)
-}
-
-pub fn message_loop() -> Result<(), Box<dyn std::error::Error>> {
-    unsafe {
-        let mut msg = std::mem::zeroed();
-        while RUNNING.load(Ordering::SeqCst) {
-            let result = GetMessageW(&mut msg, ptr::null_mut(), 0, 0);
-            if result == -1 {
-                return Err("GetMessage failed".into());
-            }
-            if result == 0 {
-                break; // WM_QUIT
-            }
-            
-            TranslateMessage(&msg);
-            DispatchMessageW(&msg);
-        }
-    }
-    Ok(())
-}
+// Everything but the error type needs Win32; elsewhere only stubs that report
+// `XClockError::Unsupported` are built, so the crate can still be a (conditional) dependency.
+
+#[cfg(windows)]
+mod builder;
+#[cfg(windows)]
+mod detect;
+mod error;
+#[cfg(windows)]
+mod explorer;
+#[cfg(windows)]
+mod ffi;
+#[cfg(windows)]
+mod hotkey;
+#[cfg(windows)]
+mod monitor;
+#[cfg(windows)]
+mod settings;
+#[cfg(windows)]
+mod tray;
+#[cfg(windows)]
+mod uia;
+#[cfg(windows)]
+mod window;
+#[cfg(not(windows))]
+mod unsupported;
+
+#[cfg(windows)]
+pub use builder::{ClockHook, ClockHookBuilder};
+#[cfg(windows)]
+pub use detect::{clock_regions, detect_clock_windows};
+pub use error::XClockError;
+#[cfg(windows)]
+pub use hotkey::{is_toggle_hotkey_registered, set_toggle_hotkey};
+#[cfg(windows)]
+pub use monitor::{
+    current_tooltip_text, is_elevated, is_paused, is_running, message_loop, pause_monitoring, pump_messages,
+    quit_message_loop, refresh_tooltip, restart_monitoring, resume_monitoring, run_on_message_loop,
+    set_detection_backend, set_hook_scope, set_tooltip_provider, start_monitoring, start_monitoring_guarded,
+    stop_monitoring, ClockHookGuard, DetectionBackend, HookScope,
+};
+#[cfg(windows)]
+pub use settings::{
+    add_secondary_timezone, clear_countdown, clear_location, clear_secondary_timezones, clear_sections,
+    reset_max_tooltip_width, reset_update_cooldown, section_order, set_activation_modifier, set_countdown,
+    set_datetime_format, set_line_decoration, set_line_decorations, set_line_separator, set_location,
+    set_max_tooltip_width, set_section_order, set_show_battery, set_show_day_of_year, set_show_memory,
+    set_show_moon_phase, set_show_network, set_time_24h, set_update_cooldown, set_uptime_precision,
+    set_week_label_format, set_week_scheme, Modifier, UptimePrecision, WeekScheme, SECTION_NAMES,
+};
+#[cfg(windows)]
+pub use tray::{add_tray_icon, remove_tray_icon};
+#[cfg(not(windows))]
+pub use unsupported::{is_running, start_monitoring, stop_monitoring};
+
+#[cfg(windows)]
+pub(crate) use monitor::{call_dll_set_text, call_dll_set_value, enable_dpi_awareness};
@@ -0,0 +1,657 @@
+// Loads and controls the hook DLL: starting, pausing and stopping monitoring, the tooltip
+// provider and the message loop that serves the hook, tray icon and hotkey.
+
+use std::ptr;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU8, Ordering};
+use std::sync::{Mutex, Once, OnceLock};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use winapi::shared::minwindef::{BOOL, FARPROC, HMODULE, UINT};
+use winapi::um::errhandlingapi::GetLastError;
+use winapi::um::libloaderapi::{FreeLibrary, GetModuleHandleW, GetProcAddress, LoadLibraryW};
+use winapi::um::handleapi::CloseHandle;
+use winapi::um::processthreadsapi::{GetCurrentProcess, GetCurrentThreadId, OpenProcessToken};
+use winapi::um::securitybaseapi::GetTokenInformation;
+use winapi::um::winnt::{TokenElevation, HANDLE, TOKEN_ELEVATION, TOKEN_QUERY};
+use winapi::um::winuser::*;
+use xclock_util::wide::{from_wide, to_wide};
+
+use crate::{explorer, hotkey, settings, tray, XClockError};
+
+// Global variables for thread communication
+static RUNNING: AtomicBool = AtomicBool::new(false);
+// Keeps message_loop alive across the stop/start of restart_monitoring
+static RESTARTING: AtomicBool = AtomicBool::new(false);
+static PAUSED: AtomicBool = AtomicBool::new(false);
+static mut HOOK_DLL: HMODULE = ptr::null_mut();
+// Thread currently inside message_loop, 0 when none
+static LOOP_THREAD_ID: AtomicU32 = AtomicU32::new(0);
+// Work handed to message_loop by run_on_message_loop, announced with WM_RUN_LOOP_TASKS
+static LOOP_TASKS: Mutex<Vec<LoopTask>> = Mutex::new(Vec::new());
+const WM_RUN_LOOP_TASKS: UINT = WM_APP + 2;
+
+static DETECTION_BACKEND: AtomicU8 = AtomicU8::new(DetectionBackend::Hook as u8);
+static HOOK_SCOPE: AtomicU8 = AtomicU8::new(HookScope::Global as u8);
+static POLL_THREAD: Mutex<Option<JoinHandle<()>>> = Mutex::new(None);
+// Full path of the hook DLL, used instead of searching for xclock_hook.dll when set
+const HOOK_DLL_ENV_VAR: &str = "XCLOCK_HOOK_DLL";
+const POLL_INTERVAL: Duration = Duration::from_millis(33); // ~30Hz
+// Right after login Explorer may not accept the hook yet; retried after 250, 500 and 1000ms
+const HOOK_INSTALL_ATTEMPTS: u32 = 4;
+const HOOK_INSTALL_FIRST_RETRY: Duration = Duration::from_millis(250);
+
+type TooltipProvider = Box<dyn Fn() -> String + Send + Sync>;
+type LoopTask = Box<dyn FnOnce() + Send>;
+static TOOLTIP_PROVIDER: OnceLock<TooltipProvider> = OnceLock::new();
+const PROVIDER_REFRESH_MS: u32 = 1000;
+
+// Signature shared by InstallHook/UninstallHook/PollTooltips in the DLL
+type HookControlFn = unsafe extern "system" fn() -> BOOL;
+type SetTextFn = unsafe extern "system" fn(*const u16) -> BOOL;
+type SetValueFn = unsafe extern "system" fn(u32) -> BOOL;
+type GenerateTextFn = unsafe extern "system" fn(*mut u16, u32) -> u32;
+type GetErrorFn = unsafe extern "system" fn() -> u32;
+
+/// How tooltip appearances are detected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DetectionBackend {
+    /// CBT hook injected through xclock_hook.dll, scoped by [`set_hook_scope`] (the default).
+    Hook = 0,
+    /// Scan for visible tooltips from a background thread; no hook is installed.
+    Poll = 1,
+    /// Try the hook first and fall back to polling if it cannot be installed.
+    Auto = 2,
+}
+
+/// Which threads the CBT hook of [`DetectionBackend::Hook`] is installed into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HookScope {
+    /// Only Explorer's taskbar thread, leaving every other process alone. Falls back to
+    /// [`HookScope::Global`] if no taskbar exists when monitoring starts. Only suitable where the
+    /// taskbar thread itself creates the clock tooltip, which isn't confirmed for Windows 11.
+    Explorer = 0,
+    /// Every GUI thread on the desktop, injecting xclock_hook.dll into every GUI process (the
+    /// default).
+    #[default]
+    Global = 1,
+}
+
+/// Selects the hook scope used by the next `start_monitoring` call.
+pub fn set_hook_scope(scope: HookScope) {
+    HOOK_SCOPE.store(scope as u8, Ordering::SeqCst);
+}
+
+fn hook_scope() -> HookScope {
+    match HOOK_SCOPE.load(Ordering::SeqCst) {
+        0 => HookScope::Explorer,
+        _ => HookScope::Global,
+    }
+}
+
+/// Selects the detection backend used by the next `start_monitoring` call.
+pub fn set_detection_backend(backend: DetectionBackend) {
+    DETECTION_BACKEND.store(backend as u8, Ordering::SeqCst);
+}
+
+fn detection_backend() -> DetectionBackend {
+    match DETECTION_BACKEND.load(Ordering::SeqCst) {
+        1 => DetectionBackend::Poll,
+        2 => DetectionBackend::Auto,
+        _ => DetectionBackend::Hook,
+    }
+}
+
+// Makes GetWindowRect report physical pixels on mixed-DPI setups so the poller and
+// detect_clock_windows see the same coordinates as Explorer. Only the first call has an effect.
+pub(crate) fn enable_dpi_awareness() {
+    static ONCE: Once = Once::new();
+    // Not exported by winapi 0.3
+    const DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2: isize = -4;
+    type SetProcessDpiAwarenessContextFn = unsafe extern "system" fn(isize) -> BOOL;
+
+    ONCE.call_once(|| unsafe {
+        // Windows 10 1703 and later; looked up at runtime so older systems can still load xclock
+        let user32 = to_wide("user32.dll");
+        let name = c"SetProcessDpiAwarenessContext";
+        let func = GetProcAddress(GetModuleHandleW(user32.as_ptr()), name.as_ptr());
+        if !func.is_null() {
+            let set_context: SetProcessDpiAwarenessContextFn = std::mem::transmute(func);
+            if set_context(DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2) != 0 {
+                log::debug!("DPI awareness set to per-monitor v2");
+                return;
+            }
+        }
+        SetProcessDPIAware();
+        log::debug!("DPI awareness set to system-aware");
+    });
+}
+
+unsafe fn load_hook_dll() -> Result<(), XClockError> {
+    if !HOOK_DLL.is_null() {
+        return Ok(()); // Already loaded
+    }
+
+    // An explicit path replaces the search below, so a missing DLL there is an error of its own
+    if let Some(dll_path) = std::env::var_os(HOOK_DLL_ENV_VAR).filter(|path| !path.is_empty()) {
+        let dll_path = dll_path.to_string_lossy().into_owned();
+        let dll_name = to_wide(&dll_path);
+        HOOK_DLL = LoadLibraryW(dll_name.as_ptr());
+        if HOOK_DLL.is_null() {
+            return Err(XClockError::DllPathLoadFailed(dll_path, GetLastError()));
+        }
+        hook_dll_loaded(&dll_path);
+        return Ok(());
+    }
+
+    // Try different possible paths for the DLL
+    let dll_paths = [
+        "xclock_hook.dll",                    // Current directory
+        "target\\release\\xclock_hook.dll",   // Build directory
+        "..\\target\\release\\xclock_hook.dll", // Parent build directory
+    ];
+
+    for dll_path in &dll_paths {
+        let dll_name = to_wide(dll_path);
+        HOOK_DLL = LoadLibraryW(dll_name.as_ptr());
+        
+        if !HOOK_DLL.is_null() {
+            hook_dll_loaded(dll_path);
+            return Ok(());
+        }
+        log::debug!("Could not load {} (error {})", dll_path, GetLastError());
+    }
+    
+    Err(XClockError::DllLoadFailed)
+}
+
+unsafe fn hook_dll_loaded(dll_path: &str) {
+    log::info!("Loaded DLL from: {}", dll_path);
+    settings::apply();
+    // Another process may still hold the mapping with an older pause state
+    let _ = call_dll_set_value("SetSuppressed", PAUSED.load(Ordering::SeqCst) as u32);
+}
+
+unsafe fn unload_hook_dll() {
+    if !HOOK_DLL.is_null() {
+        FreeLibrary(HOOK_DLL);
+        HOOK_DLL = ptr::null_mut();
+    }
+}
+
+unsafe fn get_dll_function(func_name: &str) -> Result<FARPROC, XClockError> {
+    if HOOK_DLL.is_null() {
+        return Err(XClockError::DllLoadFailed);
+    }
+
+    let func_name_cstr = std::ffi::CString::new(func_name)
+        .map_err(|_| XClockError::DllFunctionMissing(func_name.to_string()))?;
+    let func_ptr = GetProcAddress(HOOK_DLL, func_name_cstr.as_ptr());
+    
+    if func_ptr.is_null() {
+        return Err(XClockError::DllFunctionMissing(func_name.to_string()));
+    }
+
+    Ok(func_ptr)
+}
+
+unsafe fn call_dll_function(func_name: &str) -> Result<BOOL, XClockError> {
+    let func_ptr = get_dll_function(func_name)?;
+    let hook_control: HookControlFn = std::mem::transmute(func_ptr);
+    Ok(hook_control())
+}
+
+pub(crate) unsafe fn call_dll_set_text(func_name: &str, text: &str) -> Result<BOOL, XClockError> {
+    let func_ptr = get_dll_function(func_name)?;
+    let set_text: SetTextFn = std::mem::transmute(func_ptr);
+    let text_utf16 = to_wide(text);
+    Ok(set_text(text_utf16.as_ptr()))
+}
+
+pub(crate) unsafe fn call_dll_set_value(func_name: &str, value: u32) -> Result<BOOL, XClockError> {
+    let func_ptr = get_dll_function(func_name)?;
+    let set_value: SetValueFn = std::mem::transmute(func_ptr);
+    Ok(set_value(value))
+}
+
+/// Replaces the built-in uptime/week lines with the text returned by `f`.
+///
+/// The tooltip is rewritten inside Explorer, where this closure cannot run. Instead it is called
+/// on the thread that runs `start_monitoring`/`message_loop` (once at start, then every second)
+/// and the result is handed to the hook DLL. `f` must be `Send + Sync`, should return quickly and
+/// must not call back into xclock. Only the first provider set takes effect.
+pub fn set_tooltip_provider(f: impl Fn() -> String + Send + Sync + 'static) {
+    if TOOLTIP_PROVIDER.set(Box::new(f)).is_err() {
+        log::warn!("Tooltip provider already set - ignoring");
+    }
+}
+
+/// Calls the tooltip provider now instead of waiting for the next one-second refresh.
+///
+/// Useful for event-driven content. The text is used the next time the clock tooltip is shown; a
+/// tooltip that is already open keeps the text it was given. Does nothing while monitoring isn't
+/// running.
+pub fn refresh_tooltip() {
+    if RUNNING.load(Ordering::SeqCst) {
+        unsafe { publish_provider_text() };
+    }
+}
+
+// Pushes the provider's current text to the DLL; an empty string restores the built-in lines
+unsafe fn publish_provider_text() {
+    let text = TOOLTIP_PROVIDER.get().map(|provider| provider()).unwrap_or_default();
+    log::trace!("Publishing provider text: {:?}", text);
+    let _ = call_dll_set_text("SetCustomText", &text);
+}
+
+/// The lines the hook appends to the clock tooltip right now, including every enabled optional
+/// section, or the provider's text while one is set.
+///
+/// Loads xclock_hook.dll if monitoring hasn't, so it works without a clock window or a running
+/// hook. Returns an empty string if the DLL can't be loaded.
+pub fn current_tooltip_text() -> String {
+    unsafe {
+        if let Err(e) = load_hook_dll() {
+            log::warn!("Cannot generate the tooltip text: {}", e);
+            return String::new();
+        }
+        let func_ptr = match get_dll_function("GenerateTooltipText") {
+            Ok(func_ptr) => func_ptr,
+            Err(e) => {
+                log::warn!("Cannot generate the tooltip text: {}", e);
+                return String::new();
+            }
+        };
+        let generate: GenerateTextFn = std::mem::transmute(func_ptr);
+
+        // Sections can change between the two calls, so grow until the text fits
+        let mut buffer = vec![0u16; 1024];
+        loop {
+            let len = generate(buffer.as_mut_ptr(), buffer.len() as u32) as usize;
+            if len < buffer.len() {
+                return from_wide(&buffer[..len]);
+            }
+            buffer.resize(len + 1, 0);
+        }
+    }
+}
+
+/// Whether this process runs with an elevated (administrator) token.
+///
+/// A hook installed from a non-elevated process is not loaded into elevated processes, so their
+/// tooltips are left unchanged. Explorer normally isn't elevated, so the clock is still covered.
+pub fn is_elevated() -> bool {
+    unsafe {
+        let mut token: HANDLE = ptr::null_mut();
+        if OpenProcessToken(GetCurrentProcess(), TOKEN_QUERY, &mut token) == 0 {
+            return false;
+        }
+
+        let mut elevation: TOKEN_ELEVATION = std::mem::zeroed();
+        let mut size = 0;
+        let ok = GetTokenInformation(
+            token,
+            TokenElevation,
+            &mut elevation as *mut TOKEN_ELEVATION as *mut _,
+            std::mem::size_of::<TOKEN_ELEVATION>() as u32,
+            &mut size,
+        );
+        CloseHandle(token);
+        ok != 0 && elevation.TokenIsElevated != 0
+    }
+}
+
+unsafe fn install_hook() -> Result<(), XClockError> {
+    if hook_scope() == HookScope::Explorer {
+        match taskbar_thread_id() {
+            Some(thread_id) => {
+                if call_dll_set_value("InstallHookForThread", thread_id)? == 0 {
+                    return Err(XClockError::HookInstallFailed(last_install_error()));
+                }
+                log::info!("Hook installed via DLL - monitoring tooltip creation on taskbar thread {}", thread_id);
+                return Ok(());
+            }
+            None => log::warn!("No taskbar found - installing the hook globally instead"),
+        }
+    }
+
+    let result = call_dll_function("InstallHook")?;
+    if result == 0 {
+        return Err(XClockError::HookInstallFailed(last_install_error()));
+    }
+
+    log::info!("Global hook installed via DLL - monitoring tooltip creation across all processes");
+    if !is_elevated() {
+        log::warn!("Not running elevated - tooltips in elevated processes will not be extended");
+    }
+    Ok(())
+}
+
+// SetWindowsHookExW's error as recorded by the DLL; falls back to this thread's last error for a
+// DLL from before GetLastInstallError existed
+unsafe fn last_install_error() -> u32 {
+    match get_dll_function("GetLastInstallError") {
+        Ok(func_ptr) => {
+            let get_error: GetErrorFn = std::mem::transmute(func_ptr);
+            get_error()
+        }
+        Err(_) => GetLastError(),
+    }
+}
+
+// Retries failed installs with exponential backoff; other errors aren't transient and return at once
+unsafe fn install_hook_with_retry() -> Result<(), XClockError> {
+    let mut delay = HOOK_INSTALL_FIRST_RETRY;
+    let mut attempt = 1;
+    loop {
+        match install_hook() {
+            Err(XClockError::HookInstallFailed(code)) if attempt < HOOK_INSTALL_ATTEMPTS => {
+                log::info!("Hook install attempt {} failed (error {}) - retrying in {:?}", attempt, code, delay);
+                thread::sleep(delay);
+                delay *= 2;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+// Thread that owns the primary taskbar and with it the clock tooltip
+unsafe fn taskbar_thread_id() -> Option<u32> {
+    let class_name = to_wide("Shell_TrayWnd");
+    let taskbar = FindWindowW(class_name.as_ptr(), ptr::null());
+    if taskbar.is_null() {
+        return None;
+    }
+    match GetWindowThreadProcessId(taskbar, ptr::null_mut()) {
+        0 => None,
+        thread_id => Some(thread_id),
+    }
+}
+
+unsafe fn start_polling() -> Result<(), XClockError> {
+    let poll_tooltips: HookControlFn = std::mem::transmute(get_dll_function("PollTooltips")?);
+
+    RUNNING.store(true, Ordering::SeqCst);
+    let handle = thread::spawn(move || {
+        while RUNNING.load(Ordering::SeqCst) {
+            unsafe {
+                poll_tooltips();
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+    });
+    *POLL_THREAD.lock().unwrap_or_else(|e| e.into_inner()) = Some(handle);
+
+    log::info!("Polling for tooltips every {}ms - no hook installed", POLL_INTERVAL.as_millis());
+    Ok(())
+}
+
+unsafe fn stop_polling() {
+    let handle = POLL_THREAD.lock().unwrap_or_else(|e| e.into_inner()).take();
+    if let Some(handle) = handle {
+        let _ = handle.join();
+    }
+}
+
+pub fn start_monitoring() -> Result<(), XClockError> {
+    if RUNNING.load(Ordering::SeqCst) {
+        return Err(XClockError::AlreadyRunning);
+    }
+
+    enable_dpi_awareness();
+    unsafe {
+        load_hook_dll()?;
+        publish_provider_text();
+        
+        match detection_backend() {
+            DetectionBackend::Hook => install_hook_with_retry()?,
+            DetectionBackend::Poll => start_polling()?,
+            DetectionBackend::Auto => {
+                if let Err(e) = install_hook_with_retry() {
+                    log::warn!("Hook unavailable ({}), falling back to polling", e);
+                    start_polling()?;
+                }
+            }
+        }
+
+        RUNNING.store(true, Ordering::SeqCst);
+        // On this thread, so whichever of message_loop or pump_messages it runs receives WM_HOTKEY
+        hotkey::register();
+    }
+
+    Ok(())
+}
+
+pub fn stop_monitoring() {
+    // Only an explicit stop ends a pause; the next start begins unpaused
+    PAUSED.store(false, Ordering::SeqCst);
+    remove_hook();
+}
+
+// Stops monitoring but keeps the pause state, which hook_dll_loaded sends to the reloaded DLL
+fn remove_hook() {
+    RUNNING.store(false, Ordering::SeqCst);
+    wake_message_loop();
+    
+    unsafe {
+        // The poll thread calls into the DLL, so it must finish before the DLL is unloaded
+        stop_polling();
+        // Only takes effect on the thread that started monitoring, which is where restarts run
+        hotkey::unregister();
+
+        if !HOOK_DLL.is_null() {
+            let _result = call_dll_function("UninstallHook");
+            unload_hook_dll();
+            log::info!("Hook removed and DLL unloaded");
+        }
+    }
+}
+
+/// Temporarily stops tooltips from being extended. The hook stays installed, so
+/// [`is_running`] still returns `true` and [`resume_monitoring`] takes effect immediately.
+pub fn pause_monitoring() {
+    set_paused(true);
+}
+
+/// Undoes [`pause_monitoring`].
+pub fn resume_monitoring() {
+    set_paused(false);
+}
+
+pub fn is_paused() -> bool {
+    PAUSED.load(Ordering::SeqCst)
+}
+
+fn set_paused(paused: bool) {
+    PAUSED.store(paused, Ordering::SeqCst);
+    unsafe {
+        let _ = call_dll_set_value("SetSuppressed", paused as u32);
+    }
+    log::info!("Tooltip extension {}", if paused { "paused" } else { "resumed" });
+}
+
+/// Stops and starts monitoring again, e.g. after Explorer was restarted.
+///
+/// The DLL is unloaded and reloaded, so the hook is re-installed and the taskbar is located
+/// afresh on the next tooltip. A pause set with [`pause_monitoring`] stays in effect. While
+/// [`message_loop`] runs, call this from its thread, e.g. through [`run_on_message_loop`], so no
+/// other call into the DLL overlaps the reload.
+pub fn restart_monitoring() -> Result<(), XClockError> {
+    RESTARTING.store(true, Ordering::SeqCst);
+    remove_hook();
+    let result = start_monitoring();
+    RESTARTING.store(false, Ordering::SeqCst);
+    result
+}
+
+/// Stops monitoring when dropped. Returned by [`start_monitoring_guarded`].
+#[must_use = "monitoring stops as soon as the guard is dropped"]
+#[derive(Debug)]
+pub struct ClockHookGuard {
+    _private: (),
+}
+
+impl Drop for ClockHookGuard {
+    fn drop(&mut self) {
+        stop_monitoring();
+    }
+}
+
+/// Like [`start_monitoring`], but the hook is removed and the DLL unloaded when the returned
+/// guard goes out of scope.
+pub fn start_monitoring_guarded() -> Result<ClockHookGuard, XClockError> {
+    start_monitoring()?;
+    Ok(ClockHookGuard { _private: () })
+}
+
+pub fn is_running() -> bool {
+    RUNNING.load(Ordering::SeqCst)
+}
+
+pub fn message_loop() -> Result<(), XClockError> {
+    unsafe {
+        let mut msg: MSG = std::mem::zeroed();
+        LOOP_THREAD_ID.store(GetCurrentThreadId(), Ordering::SeqCst);
+        let refresh_timer = if TOOLTIP_PROVIDER.get().is_some() {
+            SetTimer(ptr::null_mut(), 0, PROVIDER_REFRESH_MS, None)
+        } else {
+            0
+        };
+        if let Err(e) = explorer::start_watching() {
+            log::warn!("Explorer restarts will not be detected: {}", e);
+        }
+
+        let mut result = Ok(());
+        // The tray icon keeps the loop alive while monitoring is stopped, so "Resume" can restart it
+        while RUNNING.load(Ordering::SeqCst) || RESTARTING.load(Ordering::SeqCst) || tray::is_active() {
+            let status = GetMessageW(&mut msg, ptr::null_mut(), 0, 0);
+            if status == -1 {
+                result = Err(XClockError::MessageLoopFailed(GetLastError()));
+                break;
+            }
+            if status == 0 {
+                break; // WM_QUIT
+            }
+
+            if refresh_timer != 0 && msg.message == WM_TIMER && msg.hwnd.is_null() && msg.wParam == refresh_timer {
+                publish_provider_text();
+                continue;
+            }
+            if msg.message == WM_RUN_LOOP_TASKS && msg.hwnd.is_null() {
+                run_loop_tasks();
+                continue;
+            }
+            if hotkey::handle(&msg) {
+                continue;
+            }
+            
+            TranslateMessage(&msg);
+            DispatchMessageW(&msg);
+        }
+
+        if refresh_timer != 0 {
+            KillTimer(ptr::null_mut(), refresh_timer);
+        }
+        explorer::stop_watching();
+        LOOP_THREAD_ID.store(0, Ordering::SeqCst);
+        // Tasks posted while the loop was ending would otherwise never run
+        run_loop_tasks();
+        result
+    }
+}
+
+/// Runs `f` on the thread inside [`message_loop`], or right away on the calling thread when no
+/// loop is running.
+///
+/// The hook DLL is loaded, called and unloaded without locking, so starting, stopping, restarting
+/// and changing settings from several threads can race. Threads other than the loop's, such as a
+/// console reader or a config file watcher, should make those calls through this function.
+pub fn run_on_message_loop(f: impl FnOnce() + Send + 'static) {
+    let thread_id = LOOP_THREAD_ID.load(Ordering::SeqCst);
+    if thread_id == 0 || thread_id == unsafe { GetCurrentThreadId() } {
+        f();
+        return;
+    }
+
+    LOOP_TASKS.lock().unwrap_or_else(|e| e.into_inner()).push(Box::new(f));
+    if unsafe { PostThreadMessageW(thread_id, WM_RUN_LOOP_TASKS, 0, 0) } == 0 {
+        // The loop has just ended; it runs what is queued on the way out, so run anything left here
+        log::debug!("Could not post to the message loop (error {})", unsafe { GetLastError() });
+        run_loop_tasks();
+    }
+}
+
+fn run_loop_tasks() {
+    let tasks = std::mem::take(&mut *LOOP_TASKS.lock().unwrap_or_else(|e| e.into_inner()));
+    for task in tasks {
+        task();
+    }
+}
+
+/// Dispatches the calling thread's pending messages and returns without waiting for new ones.
+///
+/// For hosts with their own main loop that can't block in [`message_loop`]. Returns `false` once
+/// `WM_QUIT` was received. The tooltip provider is refreshed on each call, at most once a second.
+pub fn pump_messages() -> bool {
+    static LAST_PUBLISH: Mutex<Option<Instant>> = Mutex::new(None);
+
+    unsafe {
+        if TOOLTIP_PROVIDER.get().is_some() && RUNNING.load(Ordering::SeqCst) {
+            let mut last = LAST_PUBLISH.lock().unwrap_or_else(|e| e.into_inner());
+            if last.is_none_or(|at| at.elapsed() >= Duration::from_millis(PROVIDER_REFRESH_MS as u64)) {
+                publish_provider_text();
+                *last = Some(Instant::now());
+            }
+        }
+
+        let mut msg: MSG = std::mem::zeroed();
+        while PeekMessageW(&mut msg, ptr::null_mut(), 0, 0, PM_REMOVE) != 0 {
+            if msg.message == WM_QUIT {
+                return false;
+            }
+            if hotkey::handle(&msg) {
+                continue;
+            }
+            TranslateMessage(&msg);
+            DispatchMessageW(&msg);
+        }
+    }
+
+    true
+}
+
+// GetMessageW only re-checks RUNNING after a message arrives, so give it one
+fn wake_message_loop() {
+    let thread_id = LOOP_THREAD_ID.load(Ordering::SeqCst);
+    if thread_id != 0 {
+        unsafe {
+            PostThreadMessageW(thread_id, WM_NULL, 0, 0);
+        }
+    }
+}
+
+/// Makes a running `message_loop` return `Ok(())`. Safe to call from any thread, e.g. a signal
+/// handler; does nothing when no loop is running.
+pub fn quit_message_loop() {
+    let thread_id = LOOP_THREAD_ID.load(Ordering::SeqCst);
+    if thread_id != 0 {
+        unsafe {
+            PostThreadMessageW(thread_id, WM_QUIT, 0, 0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::set_section_order;
+
+    #[test]
+    #[ignore = "needs xclock_hook.dll next to the test binary or in target\\release"]
+    fn current_tooltip_text_follows_the_section_order() {
+        set_section_order(&["week"]).expect("valid section order");
+        let text = current_tooltip_text();
+        assert!(text.starts_with("Uke "), "unexpected text {:?}", text);
+        assert_eq!(text.lines().count(), 1);
+    }
+}
@@ -0,0 +1,423 @@
+// Tooltip settings kept by the host process and pushed to the hook DLL, which shares them with
+// every process it is injected into. Setters may be called before `start_monitoring`; the stored
+// values are applied as soon as the DLL is loaded.
+
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+
+use chrono::{DateTime, Local};
+use winapi::um::winuser::{VK_CONTROL, VK_MENU, VK_SHIFT};
+
+use crate::{call_dll_set_text, call_dll_set_value, XClockError};
+
+/// Names accepted by [`set_section_order`].
+pub const SECTION_NAMES: [&str; 11] = [
+    "uptime", "week", "datetime", "battery", "memory", "timezones", "day_of_year", "moon", "sun", "network", "countdown",
+];
+
+// Mirrors the DLL's order when none has been set
+const DEFAULT_SECTION_ORDER: [&str; 3] = ["uptime", "week", "datetime"];
+// Sent as the order when every section is off; an empty order would mean the DLL's default, and
+// the DLL skips names it doesn't know
+const NO_SECTIONS: &str = "none";
+
+// Tells SetCooldownMs and SetMaxTipWidth to go back to the DLL's own default
+const DLL_DEFAULT: u32 = u32::MAX;
+
+const DEFAULT_DECORATIONS: [(&str, &str); 2] = [("uptime", "\u{23F1}"), ("week", "\u{1F4C5}")];
+
+/// How the week line numbers weeks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WeekScheme {
+    /// ISO-8601: weeks start on Monday and week 1 contains the year's first Thursday.
+    #[default]
+    Iso = 0,
+    /// US: weeks start on Sunday and week 1 contains January 1.
+    UsSundayStart = 1,
+}
+
+/// How precisely the uptime line is shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UptimePrecision {
+    /// Days, hours and minutes, e.g. `"1h 2m"`.
+    #[default]
+    Minutes = 0,
+    /// Seconds as well, e.g. `"1h 2m 5s"`.
+    Seconds = 1,
+}
+
+/// Key that must be held while hovering the clock for the extra lines to appear.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Modifier {
+    Shift,
+    Ctrl,
+    Alt,
+}
+
+impl Modifier {
+    fn virtual_key(self) -> u32 {
+        let vk = match self {
+            Modifier::Shift => VK_SHIFT,
+            Modifier::Ctrl => VK_CONTROL,
+            Modifier::Alt => VK_MENU,
+        };
+        vk as u32
+    }
+}
+
+struct Settings {
+    // None until an order is set, meaning DEFAULT_SECTION_ORDER; Some(empty) shows no sections
+    section_order: Option<Vec<String>>,
+    week_label_format: String,
+    datetime_format: String,
+    timezones: Vec<(String, i32)>,
+    update_cooldown_ms: Option<u32>,
+    max_tooltip_width: Option<u32>,
+    line_separator: String,
+    week_scheme: WeekScheme,
+    uptime_precision: UptimePrecision,
+    decorations_enabled: bool,
+    // Section name and marker; sections without an entry use DEFAULT_DECORATIONS
+    decorations: Vec<(String, String)>,
+    location: Option<(f64, f64)>,
+    countdown: Option<(String, DateTime<Local>)>,
+    time_24h: bool,
+    activation_modifier: Option<Modifier>,
+}
+
+static SETTINGS: Mutex<Settings> = Mutex::new(Settings {
+    section_order: None,
+    week_label_format: String::new(),
+    datetime_format: String::new(),
+    timezones: Vec::new(),
+    update_cooldown_ms: None,
+    max_tooltip_width: None,
+    line_separator: String::new(),
+    week_scheme: WeekScheme::Iso,
+    uptime_precision: UptimePrecision::Minutes,
+    decorations_enabled: false,
+    decorations: Vec::new(),
+    location: None,
+    countdown: None,
+    time_24h: true,
+    activation_modifier: None,
+});
+
+fn lock() -> MutexGuard<'static, Settings> {
+    SETTINGS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Sets which built-in sections the tooltip shows and in what order, e.g. `&["week", "uptime"]`.
+///
+/// Valid names are listed in [`SECTION_NAMES`]; an empty slice restores the default order.
+pub fn set_section_order(sections: &[&str]) -> Result<(), XClockError> {
+    if let Some(unknown) = sections.iter().find(|name| !SECTION_NAMES.contains(name)) {
+        return Err(XClockError::UnknownSection(unknown.to_string()));
+    }
+
+    let mut settings = lock();
+    settings.section_order = if sections.is_empty() {
+        None
+    } else {
+        Some(sections.iter().map(|name| name.to_string()).collect())
+    };
+    unsafe { push(&settings) };
+    Ok(())
+}
+
+/// Turns every section off, including the default ones. Sections enabled afterwards, e.g. with
+/// [`set_show_battery`], are then the only lines added to the tooltip.
+pub fn clear_sections() {
+    let mut settings = lock();
+    settings.section_order = Some(Vec::new());
+    unsafe { push(&settings) };
+}
+
+/// The sections the tooltip shows, in order.
+pub fn section_order() -> Vec<String> {
+    let settings = lock();
+    match &settings.section_order {
+        Some(order) => order.clone(),
+        None => DEFAULT_SECTION_ORDER.iter().map(|name| name.to_string()).collect(),
+    }
+}
+
+// Adds an optional section at the end of the current order, or removes it
+fn set_section_enabled(name: &str, enabled: bool) {
+    let mut settings = lock();
+    let order = settings
+        .section_order
+        .get_or_insert_with(|| DEFAULT_SECTION_ORDER.iter().map(|name| name.to_string()).collect());
+
+    let present = order.iter().any(|section| section == name);
+    if enabled && !present {
+        order.push(name.to_string());
+    } else if !enabled && present {
+        order.retain(|section| section != name);
+    }
+    unsafe { push(&settings) };
+}
+
+/// Shows a `"Battery: 82% (charging)"` line. Machines without a battery never show it.
+pub fn set_show_battery(enabled: bool) {
+    set_section_enabled("battery", enabled);
+}
+
+/// Shows a `"CPU: 12%, RAM: 61%"` line. CPU load is measured between successive tooltips, so
+/// the first tooltip after Explorer loads the hook shows memory only.
+pub fn set_show_memory(enabled: bool) {
+    set_section_enabled("memory", enabled);
+}
+
+/// Shows a `"Network: Connected (LAN)"` or `"Network: Offline"` line. This is the local
+/// connection state reported by WinINet, so Wi-Fi shows as LAN and a connected network without
+/// internet access still counts as connected.
+pub fn set_show_network(enabled: bool) {
+    set_section_enabled("network", enabled);
+}
+
+/// Shows a `"Day 287 of 365 (78 left)"` line; leap years count 366 days.
+pub fn set_show_day_of_year(enabled: bool) {
+    set_section_enabled("day_of_year", enabled);
+}
+
+/// Shows the current lunar phase, e.g. `"🌓 First Quarter"`. Based on the mean lunar cycle, so a
+/// phase change may show up to a day early or late.
+pub fn set_show_moon_phase(enabled: bool) {
+    set_section_enabled("moon", enabled);
+}
+
+/// Shows today's sunrise and sunset at the given location in local time, e.g.
+/// `"☀ 06:12 / 🌙 20:44"`, with dashes during polar day or night. Latitude is positive north,
+/// longitude positive east.
+pub fn set_location(latitude: f64, longitude: f64) -> Result<(), XClockError> {
+    if !(-90.0..=90.0).contains(&latitude) || !(-180.0..=180.0).contains(&longitude) {
+        return Err(XClockError::InvalidLocation);
+    }
+
+    lock().location = Some((latitude, longitude));
+    set_section_enabled("sun", true);
+    Ok(())
+}
+
+/// Removes the sunrise/sunset line added with [`set_location`].
+pub fn clear_location() {
+    lock().location = None;
+    set_section_enabled("sun", false);
+}
+
+/// Shows a `"Deadline: 2d 4h 13m"` line counting down to `target`, which turns into
+/// `"Deadline: passed"` once the time is reached. Replaces any previous countdown.
+pub fn set_countdown(label: &str, target: DateTime<Local>) {
+    // Tabs and newlines would break the line apart on its way to the DLL
+    let label = label.replace(['\t', '\n', '\r'], " ");
+    lock().countdown = Some((label, target));
+    set_section_enabled("countdown", true);
+}
+
+/// Removes the line added with [`set_countdown`].
+pub fn clear_countdown() {
+    lock().countdown = None;
+    set_section_enabled("countdown", false);
+}
+
+/// Adds a `"NYC: 09:14"` line showing the current time at a fixed UTC offset, e.g.
+/// `add_secondary_timezone("NYC", -5)`. Repeated calls stack lines in call order, and a
+/// `(+1d)`/`(-1d)` marker is added when that zone is on a different date than the local one.
+pub fn add_secondary_timezone(label: &str, offset_hours: i32) -> Result<(), XClockError> {
+    if !(-23..=23).contains(&offset_hours) {
+        return Err(XClockError::InvalidTimezoneOffset(offset_hours));
+    }
+
+    // Tabs and newlines delimit the zones on their way to the DLL
+    let label = label.replace(['\t', '\n', '\r'], " ");
+    lock().timezones.push((label, offset_hours));
+    set_section_enabled("timezones", true);
+    Ok(())
+}
+
+/// Removes all zones added with [`add_secondary_timezone`].
+pub fn clear_secondary_timezones() {
+    lock().timezones.clear();
+    set_section_enabled("timezones", false);
+}
+
+/// Sets the text of the week line. `{week}` and `{year}` are replaced with the week number and
+/// week-based year in the scheme chosen with [`set_week_scheme`] (ISO-8601 by default), e.g.
+/// `"KW {week}"`. An empty string restores the default `"Uke {week}"`.
+pub fn set_week_label_format(format: &str) {
+    let mut settings = lock();
+    settings.week_label_format = format.to_string();
+    unsafe { push(&settings) };
+}
+
+/// Prefixes tooltip lines with a glyph, by default "⏱" for uptime and "📅" for the week line.
+/// Off by default.
+pub fn set_line_decorations(enabled: bool) {
+    let mut settings = lock();
+    settings.decorations_enabled = enabled;
+    unsafe { push(&settings) };
+}
+
+/// Sets the glyph shown before a section's line when decorations are enabled, replacing the
+/// default for `uptime`/`week`; an empty marker leaves the line undecorated.
+pub fn set_line_decoration(section: &str, marker: &str) -> Result<(), XClockError> {
+    if !SECTION_NAMES.contains(&section) {
+        return Err(XClockError::UnknownSection(section.to_string()));
+    }
+
+    let mut settings = lock();
+    // Tabs and newlines delimit the entries on their way to the DLL
+    let marker = marker.replace(['\t', '\n', '\r'], " ");
+    settings.decorations.retain(|(name, _)| name != section);
+    settings.decorations.push((section.to_string(), marker));
+    unsafe { push(&settings) };
+    Ok(())
+}
+
+fn decoration_lines(settings: &Settings) -> String {
+    if !settings.decorations_enabled {
+        return String::new();
+    }
+
+    let defaults = DEFAULT_DECORATIONS
+        .iter()
+        .filter(|(section, _)| !settings.decorations.iter().any(|(name, _)| name == section))
+        .map(|(section, marker)| (section.to_string(), marker.to_string()));
+    settings
+        .decorations
+        .iter()
+        .cloned()
+        .chain(defaults)
+        .filter(|(_, marker)| !marker.is_empty())
+        .map(|(section, marker)| format!("{}\t{}", section, marker))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Selects how weeks are numbered in the week line; `{year}` then is the year the week belongs
+/// to under that scheme. Defaults to [`WeekScheme::Iso`].
+pub fn set_week_scheme(scheme: WeekScheme) {
+    let mut settings = lock();
+    settings.week_scheme = scheme;
+    unsafe { push(&settings) };
+}
+
+/// Selects whether the uptime line includes seconds. Defaults to [`UptimePrecision::Minutes`].
+///
+/// The text is generated when the tooltip appears, so the seconds don't tick while it stays open.
+pub fn set_uptime_precision(precision: UptimePrecision) {
+    let mut settings = lock();
+    settings.uptime_precision = precision;
+    unsafe { push(&settings) };
+}
+
+/// Chooses between 24-hour (`"14:05"`, the default) and 12-hour (`"02:05 PM"`) times in the
+/// date/time, secondary timezone and sunrise/sunset lines. A format set with
+/// [`set_datetime_format`] takes precedence for the date/time line.
+pub fn set_time_24h(enabled: bool) {
+    let mut settings = lock();
+    settings.time_24h = enabled;
+    unsafe { push(&settings) };
+}
+
+/// Sets the chrono `strftime`-style pattern of the date/time line, e.g. `"%A %d %B %Y"` or
+/// `"%H:%M"`. chrono writes day and month names (`%A`, `%B`) in English.
+///
+/// By default, and for an invalid pattern or an empty string, the line shows the long date and
+/// the time as the user's Windows locale writes them.
+pub fn set_datetime_format(format: &str) {
+    let mut settings = lock();
+    settings.datetime_format = format.to_string();
+    unsafe { push(&settings) };
+}
+
+/// Sets how long the hook waits before rewriting another tooltip in the same process
+/// (default 500ms). Shorter values react faster when tooltips are shown in quick succession.
+/// Durations of `u32::MAX` milliseconds or more are clamped just below it.
+pub fn set_update_cooldown(cooldown: Duration) {
+    let mut settings = lock();
+    settings.update_cooldown_ms = Some(u32::try_from(cooldown.as_millis()).unwrap_or(u32::MAX).min(u32::MAX - 1));
+    unsafe { push(&settings) };
+}
+
+/// Undoes [`set_update_cooldown`], going back to the default 500ms.
+pub fn reset_update_cooldown() {
+    let mut settings = lock();
+    settings.update_cooldown_ms = None;
+    unsafe { push(&settings) };
+}
+
+/// Caps the width of the clock tooltip at `px` pixels (default 400); longer lines wrap at word
+/// boundaries instead of stretching the tooltip. `0` or less removes the cap.
+///
+/// Only applies to the classic tooltip; Windows 11's XAML tooltip sizes itself.
+pub fn set_max_tooltip_width(px: i32) {
+    let mut settings = lock();
+    settings.max_tooltip_width = Some(px.max(0) as u32);
+    unsafe { push(&settings) };
+}
+
+/// Undoes [`set_max_tooltip_width`], going back to the default cap.
+pub fn reset_max_tooltip_width() {
+    let mut settings = lock();
+    settings.max_tooltip_width = None;
+    unsafe { push(&settings) };
+}
+
+/// Only extends the clock tooltip while `modifier` is held when it appears, e.g.
+/// `Some(Modifier::Shift)`; without the key the native tooltip is left as it is. `None`, the
+/// default, extends every clock tooltip.
+pub fn set_activation_modifier(modifier: Option<Modifier>) {
+    let mut settings = lock();
+    settings.activation_modifier = modifier;
+    unsafe { push(&settings) };
+}
+
+/// Sets the text between the tooltip's section lines, e.g. `" | "` to show them on one row.
+/// An empty string restores the default newline.
+///
+/// Without a newline in the separator the classic tooltip is no longer capped at 400 pixels, so
+/// the row doesn't wrap; an explicit [`set_max_tooltip_width`] still applies.
+pub fn set_line_separator(separator: &str) {
+    let mut settings = lock();
+    settings.line_separator = separator.to_string();
+    unsafe { push(&settings) };
+}
+
+// Sends every stored setting to the DLL after it has been loaded
+pub(crate) unsafe fn apply() {
+    push(&lock());
+}
+
+// Silently does nothing until the DLL is loaded
+unsafe fn push(settings: &Settings) {
+    let order = match &settings.section_order {
+        None => String::new(),
+        Some(order) if order.is_empty() => NO_SECTIONS.to_string(),
+        Some(order) => order.join(","),
+    };
+    log::debug!("Sending settings to the DLL: sections '{}'", order);
+    let _ = call_dll_set_text("SetSectionOrder", &order);
+    let _ = call_dll_set_text("SetWeekLabelFormat", &settings.week_label_format);
+    let _ = call_dll_set_text("SetDateTimeFormat", &settings.datetime_format);
+    let _ = call_dll_set_value("SetWeekScheme", settings.week_scheme as u32);
+    let _ = call_dll_set_value("SetTime12h", !settings.time_24h as u32);
+    let _ = call_dll_set_value("SetUptimePrecision", settings.uptime_precision as u32);
+    let _ = call_dll_set_value("SetActivationKey", settings.activation_modifier.map_or(0, Modifier::virtual_key));
+    let _ = call_dll_set_text("SetDecorations", &decoration_lines(settings));
+    let location = settings.location.map(|(latitude, longitude)| format!("{},{}", latitude, longitude));
+    let _ = call_dll_set_text("SetLocation", &location.unwrap_or_default());
+    let countdown = settings.countdown.as_ref().map(|(label, target)| format!("{}\t{}", target.timestamp(), label));
+    let _ = call_dll_set_text("SetCountdown", &countdown.unwrap_or_default());
+    let timezones: Vec<String> = settings
+        .timezones
+        .iter()
+        .map(|(label, offset_hours)| format!("{}\t{}", offset_hours, label))
+        .collect();
+    let _ = call_dll_set_text("SetTimezones", &timezones.join("\n"));
+    // Unset values are sent too, so a reset reaches the DLL
+    let _ = call_dll_set_value("SetCooldownMs", settings.update_cooldown_ms.unwrap_or(DLL_DEFAULT));
+    let _ = call_dll_set_text("SetLineSeparator", &settings.line_separator);
+    let _ = call_dll_set_value("SetMaxTipWidth", settings.max_tooltip_width.unwrap_or(DLL_DEFAULT));
+}
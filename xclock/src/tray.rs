@@ -0,0 +1,213 @@
+// Notification-area icon with a Pause/Resume/Exit context menu.
+//
+// The icon is owned by a hidden window created on the caller's thread, so its messages are
+// handled by the same `message_loop` that serves the hook.
+
+use std::ptr;
+use std::sync::atomic::{AtomicBool, AtomicPtr, Ordering};
+use winapi::shared::minwindef::{LOWORD, LPARAM, LRESULT, UINT, WPARAM};
+use winapi::shared::windef::{HWND, HWND__, POINT};
+use winapi::shared::winerror::ERROR_CLASS_ALREADY_EXISTS;
+use winapi::um::errhandlingapi::GetLastError;
+use winapi::um::libloaderapi::GetModuleHandleW;
+use winapi::um::shellapi::{Shell_NotifyIconW, NIF_ICON, NIF_MESSAGE, NIF_TIP, NIM_ADD, NIM_DELETE, NOTIFYICONDATAW};
+use winapi::um::winuser::*;
+
+use xclock_util::wide::to_wide;
+
+use crate::XClockError;
+
+const TRAY_CLASS_NAME: &str = "XClockTrayWindow";
+const TRAY_TOOLTIP: &str = "xclock - extended clock tooltip";
+const WM_TRAY_CALLBACK: UINT = WM_APP + 1;
+const TRAY_ICON_ID: UINT = 1;
+
+const ID_PAUSE: u16 = 1;
+const ID_RESUME: u16 = 2;
+const ID_EXIT: u16 = 3;
+
+static TRAY_WINDOW: AtomicPtr<HWND__> = AtomicPtr::new(ptr::null_mut());
+static TRAY_CLASS_REGISTERED: AtomicBool = AtomicBool::new(false);
+
+/// Adds the xclock icon to the notification area.
+///
+/// Must be called on the thread that runs `message_loop`, which then also keeps running while
+/// monitoring is stopped. "Pause" uses [`crate::pause_monitoring`], so the hook stays installed;
+/// "Exit" ends `message_loop` with `WM_QUIT`.
+pub fn add_tray_icon() -> Result<(), XClockError> {
+    if is_active() {
+        return Ok(());
+    }
+
+    unsafe {
+        register_tray_class()?;
+
+        let class_name = to_wide(TRAY_CLASS_NAME);
+        let hwnd = crate::window::create_hidden_window(&class_name, || {
+            TRAY_CLASS_REGISTERED.store(false, Ordering::SeqCst);
+            register_tray_class()
+        })?;
+
+        if !add_icon(hwnd) {
+            DestroyWindow(hwnd);
+            return Err(XClockError::TrayIconFailed);
+        }
+
+        TRAY_WINDOW.store(hwnd, Ordering::SeqCst);
+    }
+
+    Ok(())
+}
+
+/// Removes the notification-area icon added by [`add_tray_icon`].
+pub fn remove_tray_icon() {
+    let hwnd = TRAY_WINDOW.swap(ptr::null_mut(), Ordering::SeqCst);
+    if hwnd.is_null() {
+        return;
+    }
+
+    unsafe {
+        let mut data = notify_icon_data(hwnd);
+        Shell_NotifyIconW(NIM_DELETE, &mut data);
+        DestroyWindow(hwnd);
+        unregister_tray_class();
+    }
+}
+
+// Puts the icon back after Explorer restarted and lost it
+pub(crate) fn restore_tray_icon() {
+    let hwnd = TRAY_WINDOW.load(Ordering::SeqCst);
+    if hwnd.is_null() {
+        return;
+    }
+
+    unsafe {
+        if !add_icon(hwnd) {
+            log::warn!("Failed to restore the tray icon");
+        }
+    }
+}
+
+pub(crate) fn is_active() -> bool {
+    !TRAY_WINDOW.load(Ordering::SeqCst).is_null()
+}
+
+unsafe fn register_tray_class() -> Result<(), XClockError> {
+    if TRAY_CLASS_REGISTERED.load(Ordering::SeqCst) {
+        return Ok(());
+    }
+
+    let class_name = to_wide(TRAY_CLASS_NAME);
+    let mut wnd_class: WNDCLASSW = std::mem::zeroed();
+    wnd_class.lpfnWndProc = Some(tray_window_proc);
+    wnd_class.hInstance = GetModuleHandleW(ptr::null());
+    wnd_class.lpszClassName = class_name.as_ptr();
+
+    if RegisterClassW(&wnd_class) == 0 {
+        // Left behind by an earlier registration in this process - same window proc, so reuse it
+        let error = GetLastError();
+        if error != ERROR_CLASS_ALREADY_EXISTS {
+            return Err(XClockError::ClassRegistrationFailed(error));
+        }
+    }
+
+    TRAY_CLASS_REGISTERED.store(true, Ordering::SeqCst);
+    Ok(())
+}
+
+unsafe fn unregister_tray_class() {
+    if !TRAY_CLASS_REGISTERED.swap(false, Ordering::SeqCst) {
+        return;
+    }
+
+    let class_name = to_wide(TRAY_CLASS_NAME);
+    UnregisterClassW(class_name.as_ptr(), GetModuleHandleW(ptr::null()));
+}
+
+unsafe fn notify_icon_data(hwnd: HWND) -> NOTIFYICONDATAW {
+    let mut data: NOTIFYICONDATAW = std::mem::zeroed();
+    data.cbSize = std::mem::size_of::<NOTIFYICONDATAW>() as u32;
+    data.hWnd = hwnd;
+    data.uID = TRAY_ICON_ID;
+    data
+}
+
+unsafe fn add_icon(hwnd: HWND) -> bool {
+    let mut data = notify_icon_data(hwnd);
+    data.uFlags = NIF_MESSAGE | NIF_ICON | NIF_TIP;
+    data.uCallbackMessage = WM_TRAY_CALLBACK;
+    data.hIcon = LoadIconW(ptr::null_mut(), IDI_APPLICATION);
+    let tip = to_wide(TRAY_TOOLTIP);
+    let tip_len = tip.len().min(data.szTip.len() - 1);
+    data.szTip[..tip_len].copy_from_slice(&tip[..tip_len]);
+
+    Shell_NotifyIconW(NIM_ADD, &mut data) != 0
+}
+
+unsafe fn show_menu(hwnd: HWND) {
+    let menu = CreatePopupMenu();
+    if menu.is_null() {
+        return;
+    }
+
+    let active = crate::is_running() && !crate::is_paused();
+    let pause = to_wide("Pause");
+    let resume = to_wide("Resume");
+    let exit = to_wide("Exit");
+    AppendMenuW(menu, MF_STRING | if active { 0 } else { MF_GRAYED }, ID_PAUSE as usize, pause.as_ptr());
+    AppendMenuW(menu, MF_STRING | if active { MF_GRAYED } else { 0 }, ID_RESUME as usize, resume.as_ptr());
+    AppendMenuW(menu, MF_SEPARATOR, 0, ptr::null());
+    AppendMenuW(menu, MF_STRING, ID_EXIT as usize, exit.as_ptr());
+
+    let mut cursor = POINT { x: 0, y: 0 };
+    GetCursorPos(&mut cursor);
+
+    // Without the foreground switch the menu doesn't close when clicking elsewhere
+    SetForegroundWindow(hwnd);
+    TrackPopupMenu(menu, TPM_RIGHTBUTTON, cursor.x, cursor.y, 0, hwnd, ptr::null());
+    PostMessageW(hwnd, WM_NULL, 0, 0);
+    DestroyMenu(menu);
+}
+
+unsafe extern "system" fn tray_window_proc(hwnd: HWND, msg: UINT, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    match msg {
+        WM_TRAY_CALLBACK => {
+            let event = LOWORD(lparam as u32) as UINT;
+            if event == WM_RBUTTONUP || event == WM_CONTEXTMENU {
+                show_menu(hwnd);
+            }
+            0
+        }
+        WM_COMMAND => {
+            match LOWORD(wparam as u32) {
+                ID_PAUSE => crate::pause_monitoring(),
+                ID_RESUME if crate::is_running() => crate::resume_monitoring(),
+                ID_RESUME => {
+                    if let Err(e) = crate::start_monitoring() {
+                        log::error!("Failed to resume monitoring: {}", e);
+                    }
+                }
+                ID_EXIT => PostQuitMessage(0),
+                _ => {}
+            }
+            0
+        }
+        _ => DefWindowProcW(hwnd, msg, wparam, lparam),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tray_class_registers_again_after_unregister() {
+        unsafe {
+            register_tray_class().expect("first registration");
+            register_tray_class().expect("repeated registration");
+            unregister_tray_class();
+            register_tray_class().expect("registration after unregister");
+            unregister_tray_class();
+        }
+    }
+}
@@ -0,0 +1,143 @@
+// Finds the Windows 11 clock through UI Automation.
+//
+// Recent Windows 11 builds draw the clock with XAML inside the taskbar, so there is no clock
+// window to find by class; the only stable handle is the "ClockButton" AutomationId. winapi 0.3
+// has no UI Automation bindings, so the few IUIAutomation methods used here are declared by hand.
+//
+// Only detect_clock_windows uses this, to report the clock; tooltip matching doesn't.
+
+use std::ptr;
+use winapi::Interface;
+use winapi::shared::minwindef::LPVOID;
+use winapi::shared::windef::{HWND, RECT};
+use winapi::shared::winerror::{RPC_E_CHANGED_MODE, SUCCEEDED};
+use winapi::shared::wtypes::VT_BSTR;
+use winapi::shared::wtypesbase::CLSCTX_INPROC_SERVER;
+use winapi::um::combaseapi::{CoCreateInstance, CoInitializeEx, CoUninitialize};
+use winapi::um::oaidl::VARIANT;
+use winapi::um::objbase::COINIT_MULTITHREADED;
+use winapi::um::oleauto::{SysAllocString, VariantClear};
+use winapi::um::unknwnbase::{IUnknown, IUnknownVtbl};
+use winapi::um::winnt::HRESULT;
+use winapi::{DEFINE_GUID, RIDL};
+
+use xclock_util::wide::to_wide;
+
+/// AutomationId of the clock element in the Windows 11 taskbar.
+pub(crate) const CLOCK_AUTOMATION_ID: &str = "ClockButton";
+
+const UIA_AUTOMATION_ID_PROPERTY_ID: i32 = 30011;
+const TREE_SCOPE_DESCENDANTS: i32 = 4;
+
+DEFINE_GUID! {CLSID_CUIAUTOMATION, 0xff48dba4, 0x60ef, 0x4201, 0xaa, 0x87, 0x54, 0x10, 0x3e, 0xef, 0x59, 0x4e}
+
+RIDL! {#[uuid(0x30cbe57d, 0xd9d0, 0x452a, 0xab, 0x13, 0x7a, 0xc5, 0xac, 0x48, 0x25, 0xee)]
+interface IUIAutomation(IUIAutomationVtbl): IUnknown(IUnknownVtbl) {}}
+
+// Vtable layouts from UIAutomationClient.h; slots xclock doesn't call are left as padding
+#[repr(C)]
+struct AutomationVtbl {
+    unknown: IUnknownVtbl,
+    _compare_to_get_root: [usize; 3],
+    element_from_handle: unsafe extern "system" fn(*mut IUIAutomation, HWND, *mut *mut Element) -> HRESULT,
+    _element_from_point_to_create_false_condition: [usize; 16],
+    create_property_condition: unsafe extern "system" fn(*mut IUIAutomation, i32, VARIANT, *mut *mut IUnknown) -> HRESULT,
+}
+
+#[repr(C)]
+struct Element {
+    vtbl: *const ElementVtbl,
+}
+
+#[repr(C)]
+struct ElementVtbl {
+    unknown: IUnknownVtbl,
+    _set_focus_to_get_runtime_id: [usize; 2],
+    find_first: unsafe extern "system" fn(*mut Element, i32, *mut IUnknown, *mut *mut Element) -> HRESULT,
+    _find_all_to_get_item_status: [usize; 37],
+    get_current_bounding_rectangle: unsafe extern "system" fn(*mut Element, *mut RECT) -> HRESULT,
+}
+
+/// Screen rectangle of the "ClockButton" element inside `taskbar`, or `None` if UI Automation is
+/// unavailable or the taskbar has no such element.
+pub(crate) fn find_clock_button(taskbar: HWND) -> Option<RECT> {
+    unsafe {
+        // RPC_E_CHANGED_MODE: the thread already uses another apartment, which works just as well
+        let init = CoInitializeEx(ptr::null_mut(), COINIT_MULTITHREADED);
+        if !SUCCEEDED(init) && init != RPC_E_CHANGED_MODE {
+            log::debug!("CoInitializeEx failed: {:#x}", init);
+            return None;
+        }
+
+        let rect = find_with_automation(taskbar);
+
+        if SUCCEEDED(init) {
+            CoUninitialize();
+        }
+        rect
+    }
+}
+
+unsafe fn find_with_automation(taskbar: HWND) -> Option<RECT> {
+    let mut automation: *mut IUIAutomation = ptr::null_mut();
+    let hr = CoCreateInstance(
+        &CLSID_CUIAUTOMATION,
+        ptr::null_mut(),
+        CLSCTX_INPROC_SERVER,
+        &IUIAutomation::uuidof(),
+        &mut automation as *mut _ as *mut LPVOID,
+    );
+    if !SUCCEEDED(hr) || automation.is_null() {
+        log::debug!("UI Automation unavailable: {:#x}", hr);
+        return None;
+    }
+
+    let rect = find_in_taskbar(automation, taskbar);
+    release(automation as *mut IUnknown);
+    rect
+}
+
+unsafe fn find_in_taskbar(automation: *mut IUIAutomation, taskbar: HWND) -> Option<RECT> {
+    let vtbl = &*((*automation).lpVtbl as *const AutomationVtbl);
+
+    let mut root: *mut Element = ptr::null_mut();
+    if !SUCCEEDED((vtbl.element_from_handle)(automation, taskbar, &mut root)) || root.is_null() {
+        return None;
+    }
+
+    let id = to_wide(CLOCK_AUTOMATION_ID);
+    let mut value: VARIANT = std::mem::zeroed();
+    let variant = value.n1.n2_mut();
+    variant.vt = VT_BSTR as u16;
+    *variant.n3.bstrVal_mut() = SysAllocString(id.as_ptr());
+
+    let mut condition: *mut IUnknown = ptr::null_mut();
+    let hr = (vtbl.create_property_condition)(automation, UIA_AUTOMATION_ID_PROPERTY_ID, value, &mut condition);
+    VariantClear(&mut value);
+    if !SUCCEEDED(hr) || condition.is_null() {
+        release(root as *mut IUnknown);
+        return None;
+    }
+
+    let mut clock: *mut Element = ptr::null_mut();
+    let root_vtbl = &*(*root).vtbl;
+    let hr = (root_vtbl.find_first)(root, TREE_SCOPE_DESCENDANTS, condition, &mut clock);
+    release(condition);
+    release(root as *mut IUnknown);
+    if !SUCCEEDED(hr) || clock.is_null() {
+        return None;
+    }
+
+    let mut rect = RECT { left: 0, top: 0, right: 0, bottom: 0 };
+    let hr = ((*(*clock).vtbl).get_current_bounding_rectangle)(clock, &mut rect);
+    release(clock as *mut IUnknown);
+    if SUCCEEDED(hr) && rect.right > rect.left && rect.bottom > rect.top {
+        Some(rect)
+    } else {
+        None
+    }
+}
+
+unsafe fn release(object: *mut IUnknown) {
+    (*object).Release();
+}
@@ -0,0 +1,16 @@
+// Stand-ins for the monitoring API on platforms without Win32.
+
+use crate::XClockError;
+
+/// Always fails with [`XClockError::Unsupported`]; xclock only works on Windows.
+pub fn start_monitoring() -> Result<(), XClockError> {
+    Err(XClockError::Unsupported)
+}
+
+/// Does nothing; monitoring can't be running on this platform.
+pub fn stop_monitoring() {}
+
+/// Always `false` on this platform.
+pub fn is_running() -> bool {
+    false
+}
@@ -0,0 +1,50 @@
+// Hidden top-level windows that receive messages for the tray icon and the Explorer watcher.
+
+use std::ptr;
+use winapi::shared::windef::HWND;
+use winapi::um::errhandlingapi::GetLastError;
+use winapi::um::libloaderapi::GetModuleHandleW;
+use winapi::um::winuser::{CreateWindowExW, WS_OVERLAPPED};
+
+use crate::XClockError;
+
+/// Creates a hidden window of the already registered `class_name`.
+///
+/// If creation fails, `register` is called to register the class again before one more attempt,
+/// since the class can be gone by then (e.g. unregistered while the window was down).
+pub(crate) unsafe fn create_hidden_window(
+    class_name: &[u16],
+    register: impl Fn() -> Result<(), XClockError>,
+) -> Result<HWND, XClockError> {
+    let hwnd = create(class_name);
+    if !hwnd.is_null() {
+        return Ok(hwnd);
+    }
+
+    log::warn!("CreateWindowExW failed (error {}) - registering the window class again", GetLastError());
+    register()?;
+    let hwnd = create(class_name);
+    if hwnd.is_null() {
+        let error = GetLastError();
+        log::error!("CreateWindowExW failed again (error {})", error);
+        return Err(XClockError::WindowCreationFailed(error));
+    }
+    Ok(hwnd)
+}
+
+unsafe fn create(class_name: &[u16]) -> HWND {
+    CreateWindowExW(
+        0,
+        class_name.as_ptr(),
+        class_name.as_ptr(),
+        WS_OVERLAPPED,
+        0,
+        0,
+        0,
+        0,
+        ptr::null_mut(),
+        ptr::null_mut(),
+        GetModuleHandleW(ptr::null()),
+        ptr::null_mut(),
+    )
+}